@@ -1,19 +1,241 @@
+use crate::editorconfig::EditorConfigCache;
 use crate::opt::Opt;
 use crate::verbose_println;
-use anyhow::{Context, Result};
+use anyhow::{bail, format_err, Context, Result};
 use directories::{ProjectDirs, UserDirs};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::collections::HashMap;
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
-use stylua_lib::Config;
+use std::rc::Rc;
+use std::sync::Arc;
+use stylua_lib::{Config, Error, LuaVersion};
+use toml::value::{Table, Value};
 
-static CONFIG_FILE_NAME: [&str; 2] = ["stylua.toml", ".stylua.toml"];
+pub static CONFIG_FILE_NAME: [&str; 2] = ["stylua.toml", ".stylua.toml"];
 
-fn read_config_file(path: &Path) -> Result<Config> {
-    let contents = fs::read_to_string(path).context("Failed to read config file")?;
-    let config = toml::from_str(&contents).context("Config file not in correct format")?;
+/// A set of options within an `[[overrides]]` table, applied on top of the rest of the configuration file
+/// for any files matching its globs
+struct ConfigOverride {
+    globs: GlobSet,
+    options: Table,
+}
 
-    Ok(config)
+/// A configuration file, along with any overrides present within it.
+/// The default value represents the default configuration, used when no configuration file is found.
+#[derive(Default)]
+pub struct ConfigFile {
+    /// The directory containing the configuration file. Override globs are matched relative to this directory
+    directory: PathBuf,
+    /// The configuration file and any files it extends, which the configuration was read from
+    sources: Vec<PathBuf>,
+    options: Table,
+    overrides: Vec<ConfigOverride>,
+    config: Config,
+}
+
+impl ConfigFile {
+    /// The overrides which apply to the file at the given path, in the order they should be applied
+    fn matching_overrides<'b>(
+        &'b self,
+        path: &'b Path,
+    ) -> impl Iterator<Item = &'b ConfigOverride> {
+        let relative_path = path.strip_prefix(&self.directory).unwrap_or(path);
+        self.overrides
+            .iter()
+            .filter(move |config_override| config_override.globs.is_match(relative_path))
+    }
+
+    /// Whether the configuration file, or any of its overrides matching the path, sets the syntax for the file
+    fn sets_syntax(&self, path: &Path) -> bool {
+        self.options.contains_key("syntax")
+            || self
+                .matching_overrides(path)
+                .any(|config_override| config_override.options.contains_key("syntax"))
+    }
+
+    /// Resolves the configuration to use for the file at the given path, applying any overrides matching the path.
+    /// The given `.editorconfig` options are used where the configuration file does not set them.
+    pub fn config_for_path(&self, path: &Path, editorconfig_options: Table) -> Result<Config> {
+        let mut overrides = self.matching_overrides(path).peekable();
+
+        if editorconfig_options.is_empty() && overrides.peek().is_none() {
+            return Ok(self.config);
+        }
+
+        let mut options = editorconfig_options;
+        for (key, value) in self
+            .options
+            .iter()
+            .chain(overrides.flat_map(|config_override| config_override.options.iter()))
+        {
+            options.insert(key.to_owned(), value.to_owned());
+        }
+
+        parse_options(options)
+            .with_context(|| format!("Could not apply overrides for {}", path.display()))
+    }
+}
+
+/// Deserializes a table of configuration options into a `Config`
+fn parse_options(options: Table) -> Result<Config, toml::de::Error> {
+    Value::Table(options).try_into()
+}
+
+/// Parses an `[[overrides]]` table, checking its options are valid when applied on top of the base options
+fn parse_override(value: Value, base_options: &Table) -> Result<ConfigOverride> {
+    let mut options = match value {
+        Value::Table(options) => options,
+        _ => bail!("each override must be a table"),
+    };
+
+    let patterns = match options.remove("files") {
+        Some(Value::String(pattern)) => vec![pattern],
+        Some(Value::Array(patterns)) => patterns
+            .into_iter()
+            .map(|pattern| match pattern {
+                Value::String(pattern) => Ok(pattern),
+                _ => Err(format_err!(
+                    "override `files` must be a list of glob patterns"
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Some(_) => bail!("override `files` must be a list of glob patterns"),
+        None => bail!("override is missing a `files` list of glob patterns"),
+    };
+
+    let mut globs = GlobSetBuilder::new();
+    for pattern in &patterns {
+        globs.add(
+            Glob::new(pattern).with_context(|| format!("cannot parse glob pattern {}", pattern))?,
+        );
+    }
+
+    let mut merged_options = base_options.clone();
+    for (key, value) in &options {
+        merged_options.insert(key.to_owned(), value.to_owned());
+    }
+    parse_options(merged_options)
+        .with_context(|| format!("override for {} is invalid", patterns.join(", ")))?;
+
+    Ok(ConfigOverride {
+        globs: globs.build()?,
+        options,
+    })
+}
+
+/// Reads the options within a configuration file, merging them on top of the options of any file it `extends`.
+/// Overrides from the extended file are applied before any overrides in the file itself.
+/// `visited` holds the files already being read, so that cyclic `extends` can be detected,
+/// and every file read is added to `sources`.
+fn read_config_options(
+    path: &Path,
+    visited: &mut Vec<PathBuf>,
+    sources: &mut Vec<PathBuf>,
+) -> Result<Table> {
+    let canonical_path = path
+        .canonicalize()
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    if visited.contains(&canonical_path) {
+        bail!(
+            "Config file {} has a cyclic `extends`: {}",
+            path.display(),
+            visited
+                .iter()
+                .chain(std::iter::once(&canonical_path))
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+    }
+
+    sources.push(canonical_path.clone());
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {}", path.display()))?;
+    let mut options: Table = toml::from_str(&contents)
+        .with_context(|| format!("Config file {} not in correct format", path.display()))?;
+
+    let base_path = match options.remove("extends") {
+        Some(Value::String(base_path)) => base_path,
+        Some(_) => bail!(
+            "Config file {} not in correct format: `extends` must be a path",
+            path.display()
+        ),
+        None => return Ok(options),
+    };
+
+    // The extended path is relative to the directory of the file extending it
+    let base_path = match canonical_path.parent() {
+        Some(directory) => directory.join(base_path),
+        None => PathBuf::from(base_path),
+    };
+
+    visited.push(canonical_path);
+    let mut base_options =
+        read_config_options(&base_path, visited, sources).with_context(|| {
+            format!(
+                "Failed to load config file {} extended by {}",
+                base_path.display(),
+                path.display()
+            )
+        })?;
+    visited.pop();
+
+    // Overrides are combined, rather than replacing those of the extended file
+    let overrides = match (
+        base_options.remove("overrides"),
+        options.remove("overrides"),
+    ) {
+        (Some(Value::Array(mut base_overrides)), Some(Value::Array(overrides))) => {
+            base_overrides.extend(overrides);
+            Some(Value::Array(base_overrides))
+        }
+        (base_overrides, overrides) => overrides.or(base_overrides),
+    };
+
+    for (key, value) in options {
+        base_options.insert(key, value);
+    }
+    if let Some(overrides) = overrides {
+        base_options.insert("overrides".to_owned(), overrides);
+    }
+
+    Ok(base_options)
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile> {
+    let mut sources = Vec::new();
+    let mut options = read_config_options(path, &mut Vec::new(), &mut sources)?;
+
+    let overrides = options.remove("overrides");
+    let config = parse_options(options.clone()).context("Config file not in correct format")?;
+
+    let overrides = match overrides {
+        Some(Value::Array(overrides)) => overrides
+            .into_iter()
+            .map(|config_override| parse_override(config_override, &options))
+            .collect::<Result<Vec<_>>>()
+            .context("Config file not in correct format")?,
+        Some(_) => {
+            bail!("Config file not in correct format: `overrides` must be an array of tables")
+        }
+        None => Vec::new(),
+    };
+
+    let directory = path
+        .canonicalize()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
+
+    Ok(ConfigFile {
+        directory,
+        sources,
+        options,
+        overrides,
+        config,
+    })
 }
 
 /// Searches the directory for the configuration toml file (i.e. `stylua.toml` or `.stylua.toml`)
@@ -28,7 +250,7 @@ fn find_toml_file(directory: &Path) -> Option<PathBuf> {
     None
 }
 
-fn find_config_file(mut directory: PathBuf, recursive: bool) -> Result<Option<Config>> {
+fn find_config_file(mut directory: PathBuf, recursive: bool) -> Result<Option<ConfigFile>> {
     let config_file = find_toml_file(&directory);
     match config_file {
         Some(file_path) => read_config_file(&file_path).map(Some),
@@ -44,7 +266,21 @@ fn find_config_file(mut directory: PathBuf, recursive: bool) -> Result<Option<Co
     }
 }
 
-pub fn load_config(opt: &Opt) -> Result<Config> {
+pub fn load_config(opt: &Opt) -> Result<ConfigFile> {
+    let current_dir = match &opt.stdin_filepath {
+        Some(file_path) => file_path
+            .parent()
+            .context("Could not find current directory from provided stdin filepath")?
+            .to_path_buf(),
+        None => env::current_dir().context("Could not find current directory")?,
+    };
+
+    load_config_from_directory(current_dir, opt)
+}
+
+/// Loads the configuration to use for files within the provided directory.
+/// If an explicit config path was provided, it is always used. Otherwise, the directory is searched for a configuration file.
+fn load_config_from_directory(current_dir: PathBuf, opt: &Opt) -> Result<ConfigFile> {
     match &opt.config_path {
         Some(config_path) => {
             verbose_println!(
@@ -55,13 +291,6 @@ pub fn load_config(opt: &Opt) -> Result<Config> {
             read_config_file(config_path)
         }
         None => {
-            let current_dir = match &opt.stdin_filepath {
-                Some(file_path) => file_path
-                    .parent()
-                    .context("Could not find current directory from provided stdin filepath")?
-                    .to_path_buf(),
-                None => env::current_dir().context("Could not find current directory")?,
-            };
             verbose_println!(
                 opt.verbose,
                 "config: starting config search from {} - recurisvely searching parents: {}",
@@ -99,7 +328,7 @@ pub fn load_config(opt: &Opt) -> Result<Config> {
 
                     // Fallback to a default configuration
                     verbose_println!(opt.verbose, "config: falling back to default config");
-                    Ok(Config::default())
+                    Ok(ConfigFile::default())
                 }
             }
         }
@@ -125,6 +354,592 @@ pub fn load_overrides(config: Config, opt: &Opt) -> Config {
     if let Some(quote_style) = opt.format_opts.quote_style {
         new_config = new_config.with_quote_style(quote_style.into());
     };
+    if let Some(syntax) = opt.format_opts.syntax {
+        new_config = new_config.with_syntax(syntax.into());
+    };
 
     new_config
 }
+
+/// Resolves the configuration to use for each file being formatted, from the configuration file nearest to that file.
+/// Configuration files are only searched for up to a root directory (the current directory, unless otherwise provided),
+/// unless searching parent directories is enabled. Each directory is only searched once, and any configuration or `.editorconfig` file
+/// found is cached.
+pub struct ConfigResolver {
+    opt: Arc<Opt>,
+    current_dir: PathBuf,
+    /// The configuration used for any file which has no nearer configuration file, for each root directory
+    fallbacks: HashMap<PathBuf, Rc<ConfigFile>>,
+    directories: HashMap<PathBuf, Option<Rc<ConfigFile>>>,
+    editorconfig: EditorConfigCache,
+}
+
+impl ConfigResolver {
+    pub fn new(opt: Arc<Opt>) -> Result<Self> {
+        let current_dir = env::current_dir().context("Could not find current directory")?;
+        let current_dir = current_dir.canonicalize().unwrap_or(current_dir);
+
+        let mut fallbacks = HashMap::new();
+        fallbacks.insert(current_dir.clone(), Rc::new(load_config(&opt)?));
+
+        Ok(Self {
+            opt,
+            current_dir,
+            fallbacks,
+            directories: HashMap::new(),
+            editorconfig: EditorConfigCache::default(),
+        })
+    }
+
+    /// Clears all cached configuration, so that configuration files are re-read when next needed
+    pub fn clear(&mut self) {
+        self.fallbacks.clear();
+        self.directories.clear();
+        self.editorconfig.clear();
+    }
+
+    /// Whether any cached configuration was read from the file at the given path,
+    /// either as a configuration file or as a file extended by one
+    pub fn is_config_source(&self, path: &Path) -> bool {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.fallbacks
+            .values()
+            .chain(self.directories.values().flatten())
+            .any(|config_file| config_file.sources.contains(&path))
+    }
+
+    /// Returns the configuration used for files under the root directory which have no nearer configuration file
+    fn fallback(&mut self, root: &Path) -> Result<Rc<ConfigFile>> {
+        if !self.fallbacks.contains_key(root) {
+            let config_file = load_config_from_directory(root.to_path_buf(), &self.opt)?;
+            self.fallbacks
+                .insert(root.to_path_buf(), Rc::new(config_file));
+        }
+
+        Ok(self.fallbacks[root].clone())
+    }
+
+    /// Finds the configuration file nearest to the given directory, searching no further than the root directory
+    fn find_nearest_config_file(
+        &mut self,
+        directory: &Path,
+        root: &Path,
+    ) -> Result<Option<Rc<ConfigFile>>> {
+        // An explicitly provided configuration file is always used
+        if self.opt.config_path.is_some()
+            || (!self.opt.search_parent_directories && !directory.starts_with(root))
+        {
+            return Ok(None);
+        }
+
+        for ancestor in directory.ancestors() {
+            if !self.directories.contains_key(ancestor) {
+                let config_file = match find_toml_file(ancestor) {
+                    Some(file_path) => {
+                        verbose_println!(
+                            self.opt.verbose,
+                            "config: found config file at {}",
+                            file_path.display()
+                        );
+                        Some(Rc::new(read_config_file(&file_path).with_context(
+                            || format!("Could not read config file {}", file_path.display()),
+                        )?))
+                    }
+                    None => None,
+                };
+                self.directories.insert(ancestor.to_path_buf(), config_file);
+            }
+
+            if let Some(config_file) = &self.directories[ancestor] {
+                return Ok(Some(config_file.clone()));
+            }
+
+            if ancestor == root && !self.opt.search_parent_directories {
+                break;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Resolves the configuration for the file at the given path, including any overrides provided by command line options
+    pub fn config_for_file(&mut self, path: &Path) -> Result<ResolvedConfig> {
+        let root = self.current_dir.clone();
+        self.config_for_file_within(path, &root)
+    }
+
+    /// Resolves the configuration for the file at the given path, searching for configuration files no further than the root directory.
+    /// Files outside of the root directory use the configuration found in it.
+    pub fn config_for_file_within(&mut self, path: &Path, root: &Path) -> Result<ResolvedConfig> {
+        let path = path
+            .canonicalize()
+            .unwrap_or_else(|_| self.current_dir.join(path));
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+
+        let config_file = match path.parent() {
+            Some(directory) => self.find_nearest_config_file(directory, &root)?,
+            None => None,
+        };
+        let config_file = match config_file {
+            Some(config_file) => config_file,
+            None => self.fallback(&root)?,
+        };
+
+        // Unless disabled, options from any `.editorconfig` sections matching the file are used where the
+        // configuration file does not set them
+        let editorconfig_options = if self.opt.no_editorconfig {
+            Table::new()
+        } else {
+            self.editorconfig.read_options(&path)?
+        };
+
+        let config = load_overrides(
+            config_file.config_for_path(&path, editorconfig_options)?,
+            &self.opt,
+        );
+        verbose_println!(
+            self.opt.verbose,
+            "config: using {:?} for {}",
+            config,
+            path.display()
+        );
+
+        Ok(ResolvedConfig {
+            config,
+            syntax_is_explicit: self.opt.format_opts.syntax.is_some()
+                || config_file.sets_syntax(&path),
+        })
+    }
+
+    /// Resolves the configuration for code provided through stdin, using the stdin filepath if provided
+    pub fn config_for_stdin(&mut self) -> Result<ResolvedConfig> {
+        let opt = self.opt.clone();
+        match &opt.stdin_filepath {
+            Some(file_path) => self.config_for_file(file_path),
+            None => {
+                let root = self.current_dir.clone();
+                let fallback = self.fallback(&root)?;
+                Ok(ResolvedConfig {
+                    config: load_overrides(fallback.config, &opt),
+                    syntax_is_explicit: opt.format_opts.syntax.is_some()
+                        || fallback.options.contains_key("syntax"),
+                })
+            }
+        }
+    }
+}
+
+/// The configuration resolved for a file
+#[derive(Clone, Copy)]
+pub struct ResolvedConfig {
+    pub config: Config,
+    /// Whether the syntax was set through `--syntax` or a configuration file.
+    /// If not, the syntax can be detected from the file itself.
+    pub syntax_is_explicit: bool,
+}
+
+impl ResolvedConfig {
+    /// Returns the configurations to try formatting the file with, in order.
+    /// If the syntax was set explicitly, only that syntax is used. Otherwise, the syntax detected for the file is
+    /// tried first, followed by every other syntax supported by this build of StyLua.
+    pub fn candidate_configs(self, path: Option<&Path>) -> Vec<Config> {
+        if self.syntax_is_explicit {
+            return vec![self.config];
+        }
+
+        let detected = detect_syntax(path);
+        std::iter::once(detected)
+            .chain(
+                [LuaVersion::Lua51, LuaVersion::Lua52, LuaVersion::Luau]
+                    .iter()
+                    .copied()
+                    .filter(|syntax| *syntax != detected && syntax.is_supported()),
+            )
+            .map(|syntax| self.config.with_syntax(syntax))
+            .collect()
+    }
+
+    /// Formats the file using each of its candidate configurations in turn, until the code parses.
+    /// If the code fails to parse as every syntax, the error from the first syntax tried is returned.
+    pub fn format_with<T>(
+        self,
+        path: Option<&Path>,
+        mut format: impl FnMut(Config) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut first_error = None;
+
+        for config in self.candidate_configs(path) {
+            match format(config) {
+                Err(error @ Error::ParseError { .. }) => {
+                    first_error.get_or_insert(error);
+                }
+                result => return result,
+            }
+        }
+
+        Err(first_error.expect("there is always at least one candidate configuration"))
+    }
+}
+
+/// Detects the syntax of a file from its file extension, where `.luau` files are parsed as Luau and all others as Lua51.
+/// A `-- !syntax <version>` header comment is applied when formatting, as inline configuration, so takes precedence.
+pub fn detect_syntax(path: Option<&Path>) -> LuaVersion {
+    match path.and_then(Path::extension) {
+        Some(extension) if extension == "luau" && LuaVersion::Luau.is_supported() => {
+            LuaVersion::Luau
+        }
+        _ => LuaVersion::Lua51,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+    use stylua_lib::IndentType;
+    use tempfile::TempDir;
+
+    fn write_file(directory: &Path, path: &str, contents: &str) {
+        let path = directory.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn opt(args: &[&str]) -> Arc<Opt> {
+        Arc::new(Opt::from_iter(
+            ["stylua", "--no-editorconfig"].iter().chain(args),
+        ))
+    }
+
+    fn assert_config(resolved: ResolvedConfig, expected: Config) {
+        assert_eq!(format!("{:?}", resolved.config), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn test_overrides_match_relative_to_config_directory() {
+        let dir = TempDir::new().unwrap();
+        write_file(
+            dir.path(),
+            "project/stylua.toml",
+            "[[overrides]]\nfiles = \"vendor/**\"\ncolumn_width = 80\n",
+        );
+
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        let root = dir.path().join("project");
+
+        let vendored = resolver
+            .config_for_file_within(&root.join("vendor/lib.lua"), &root)
+            .unwrap();
+        assert_config(vendored, Config::default().with_column_width(80));
+
+        let nested = resolver
+            .config_for_file_within(&root.join("src/vendor/lib.lua"), &root)
+            .unwrap();
+        assert_config(nested, Config::default());
+    }
+
+    #[test]
+    fn test_override_precedence() {
+        let dir = TempDir::new().unwrap();
+        write_file(
+            dir.path(),
+            "stylua.toml",
+            "column_width = 100\nindent_width = 2\n\n\
+             [[overrides]]\nfiles = [\"*.lua\"]\ncolumn_width = 80\nindent_width = 3\n\n\
+             [[overrides]]\nfiles = [\"main.lua\"]\ncolumn_width = 60\n",
+        );
+        let file = dir.path().join("main.lua");
+
+        // Later overrides take precedence over earlier ones, which take precedence over the rest of the file
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        let resolved = resolver.config_for_file_within(&file, dir.path()).unwrap();
+        assert_config(
+            resolved,
+            Config::default().with_column_width(60).with_indent_width(3),
+        );
+
+        // Command line options take precedence over any overrides
+        let mut resolver = ConfigResolver::new(opt(&["--column-width", "40"])).unwrap();
+        let resolved = resolver.config_for_file_within(&file, dir.path()).unwrap();
+        assert_config(
+            resolved,
+            Config::default().with_column_width(40).with_indent_width(3),
+        );
+    }
+
+    #[test]
+    fn test_nearest_config_file_is_used() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), "stylua.toml", "column_width = 100\n");
+        write_file(dir.path(), "vendor/.stylua.toml", "column_width = 80\n");
+
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        let root = dir.path();
+
+        let resolved = resolver
+            .config_for_file_within(&root.join("vendor/lib/init.lua"), root)
+            .unwrap();
+        assert_config(resolved, Config::default().with_column_width(80));
+
+        let resolved = resolver
+            .config_for_file_within(&root.join("src/init.lua"), root)
+            .unwrap();
+        assert_config(resolved, Config::default().with_column_width(100));
+    }
+
+    #[test]
+    fn test_file_outside_root_uses_fallback() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), "project/stylua.toml", "column_width = 100\n");
+        write_file(dir.path(), "other/stylua.toml", "column_width = 80\n");
+
+        let root = dir.path().join("project");
+        let file = dir.path().join("other/init.lua");
+
+        // Configuration files outside of the root are not used, instead falling back to the root's configuration
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        let resolved = resolver.config_for_file_within(&file, &root).unwrap();
+        assert_config(resolved, Config::default().with_column_width(100));
+
+        // Unless we are searching parent directories
+        let mut resolver = ConfigResolver::new(opt(&["--search-parent-directories"])).unwrap();
+        let resolved = resolver.config_for_file_within(&file, &root).unwrap();
+        assert_config(resolved, Config::default().with_column_width(80));
+    }
+
+    #[test]
+    fn test_config_file_takes_precedence_over_editorconfig() {
+        let dir = TempDir::new().unwrap();
+        write_file(
+            dir.path(),
+            ".editorconfig",
+            "root = true\n\n[*.lua]\nindent_style = space\nindent_size = 2\nmax_line_length = 80\n",
+        );
+        write_file(dir.path(), "stylua.toml", "indent_width = 3\n");
+        let file = dir.path().join("init.lua");
+
+        // Options set in stylua.toml take precedence, with any others taken from .editorconfig
+        let mut resolver = ConfigResolver::new(Arc::new(Opt::from_iter(&["stylua"]))).unwrap();
+        let resolved = resolver.config_for_file_within(&file, dir.path()).unwrap();
+        assert_config(
+            resolved,
+            Config::default()
+                .with_indent_type(IndentType::Spaces)
+                .with_indent_width(3)
+                .with_column_width(80),
+        );
+
+        // .editorconfig files are not read when disabled
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        let resolved = resolver.config_for_file_within(&file, dir.path()).unwrap();
+        assert_config(resolved, Config::default().with_indent_width(3));
+    }
+
+    #[test]
+    fn test_extends_merges_options() {
+        let dir = TempDir::new().unwrap();
+        write_file(
+            dir.path(),
+            "shared/stylua.toml",
+            "column_width = 80\nindent_width = 2\n",
+        );
+        write_file(
+            dir.path(),
+            "project/stylua.toml",
+            "extends = \"../shared/stylua.toml\"\ncolumn_width = 100\n",
+        );
+
+        let config_file = read_config_file(&dir.path().join("project/stylua.toml")).unwrap();
+        assert_eq!(
+            format!("{:?}", config_file.config),
+            format!(
+                "{:?}",
+                Config::default()
+                    .with_column_width(100)
+                    .with_indent_width(2)
+            )
+        );
+        assert_eq!(
+            config_file.sources,
+            vec![
+                dir.path()
+                    .join("project/stylua.toml")
+                    .canonicalize()
+                    .unwrap(),
+                dir.path()
+                    .join("shared/stylua.toml")
+                    .canonicalize()
+                    .unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extends_merges_overrides() {
+        let dir = TempDir::new().unwrap();
+        write_file(
+            dir.path(),
+            "base.toml",
+            "[[overrides]]\nfiles = \"*.lua\"\ncolumn_width = 70\nindent_width = 3\n",
+        );
+        write_file(
+            dir.path(),
+            "stylua.toml",
+            "extends = \"base.toml\"\n\n[[overrides]]\nfiles = \"main.lua\"\ncolumn_width = 60\n",
+        );
+
+        // Overrides from the extended file are kept, with those in the extending file applied last
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        let resolved = resolver
+            .config_for_file_within(&dir.path().join("main.lua"), dir.path())
+            .unwrap();
+        assert_config(
+            resolved,
+            Config::default().with_column_width(60).with_indent_width(3),
+        );
+
+        let resolved = resolver
+            .config_for_file_within(&dir.path().join("other.lua"), dir.path())
+            .unwrap();
+        assert_config(
+            resolved,
+            Config::default().with_column_width(70).with_indent_width(3),
+        );
+    }
+
+    #[test]
+    fn test_cyclic_extends() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), "a.toml", "extends = \"b.toml\"\n");
+        write_file(dir.path(), "b.toml", "extends = \"a.toml\"\n");
+
+        let error = match read_config_file(&dir.path().join("a.toml")) {
+            Ok(_) => panic!("expected cyclic `extends` to fail"),
+            Err(error) => format!("{:#}", error),
+        };
+        assert!(error.contains("has a cyclic `extends`"), "{}", error);
+    }
+
+    #[test]
+    fn test_missing_extended_file() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), "stylua.toml", "extends = \"missing.toml\"\n");
+
+        let error = match read_config_file(&dir.path().join("stylua.toml")) {
+            Ok(_) => panic!("expected missing extended file to fail"),
+            Err(error) => format!("{:#}", error),
+        };
+        assert!(error.contains("missing.toml"), "{}", error);
+    }
+
+    #[test]
+    fn test_extended_files_are_config_sources() {
+        let dir = TempDir::new().unwrap();
+        write_file(dir.path(), "shared/base.toml", "column_width = 80\n");
+        write_file(
+            dir.path(),
+            "project/stylua.toml",
+            "extends = \"../shared/base.toml\"\n",
+        );
+        write_file(dir.path(), "shared/unrelated.toml", "column_width = 80\n");
+
+        let root = dir.path().join("project");
+        let mut resolver = ConfigResolver::new(opt(&[])).unwrap();
+        resolver
+            .config_for_file_within(&root.join("init.lua"), &root)
+            .unwrap();
+
+        assert!(resolver.is_config_source(&root.join("stylua.toml")));
+        assert!(resolver.is_config_source(&dir.path().join("shared/base.toml")));
+        assert!(!resolver.is_config_source(&dir.path().join("shared/unrelated.toml")));
+    }
+
+    #[test]
+    fn test_detect_syntax_from_extension() {
+        let expected = if LuaVersion::Luau.is_supported() {
+            LuaVersion::Luau
+        } else {
+            LuaVersion::Lua51
+        };
+        assert_eq!(detect_syntax(Some(Path::new("file.luau"))), expected);
+        assert_eq!(
+            detect_syntax(Some(Path::new("file.lua"))),
+            LuaVersion::Lua51
+        );
+        assert_eq!(detect_syntax(None), LuaVersion::Lua51);
+    }
+
+    #[test]
+    fn test_explicit_syntax_is_not_detected() {
+        let resolved = ResolvedConfig {
+            config: Config::default().with_syntax(LuaVersion::Lua51),
+            syntax_is_explicit: true,
+        };
+        let configs = resolved.candidate_configs(Some(Path::new("file.luau")));
+        assert_eq!(configs.len(), 1);
+        assert!(format!("{:?}", configs[0]).contains("syntax: Lua51"));
+    }
+
+    #[test]
+    fn test_detected_syntax_is_tried_first() {
+        let resolved = ResolvedConfig {
+            config: Config::default(),
+            syntax_is_explicit: false,
+        };
+        let syntaxes: Vec<String> = resolved
+            .candidate_configs(Some(Path::new("file.luau")))
+            .iter()
+            .map(|config| format!("{:?}", config))
+            .collect();
+
+        let mut expected = Vec::new();
+        if LuaVersion::Luau.is_supported() {
+            expected.push("syntax: Luau");
+        }
+        expected.push("syntax: Lua51");
+        if LuaVersion::Lua52.is_supported() {
+            expected.push("syntax: Lua52");
+        }
+
+        assert_eq!(syntaxes.len(), expected.len());
+        for (config, syntax) in syntaxes.iter().zip(expected) {
+            assert!(config.contains(syntax), "{}", config);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "luau", feature = "lua52"))]
+    fn test_syntax_falls_back_on_parse_error() {
+        use stylua_lib::OutputVerification;
+
+        let format = |code: &str, syntax_is_explicit: bool| {
+            let resolved = ResolvedConfig {
+                config: Config::default().with_syntax(LuaVersion::Luau),
+                syntax_is_explicit,
+            };
+            resolved.format_with(Some(Path::new("file.luau")), |config| {
+                stylua_lib::format_code(code, config, None, OutputVerification::None)
+            })
+        };
+
+        // `goto` is not Luau syntax, so the file is formatted as Lua52 instead
+        let code = "goto  continue\n::continue::\n";
+        assert_eq!(
+            format(code, false).unwrap(),
+            "goto continue\n::continue::\n"
+        );
+
+        // No other syntax is tried when the syntax is set explicitly
+        let error = format(code, true).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`goto` statements are Lua52 syntax"));
+
+        // If no syntax can parse the code, the error from the detected syntax is reported
+        let error = format("local x: number = 1\ngoto continue\n", false).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`goto` statements are Lua52 syntax"));
+        assert!(error.to_string().contains("set to Luau"));
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use ec4rs::property::{EndOfLine, IndentSize, IndentStyle, MaxLineLen};
+use ec4rs::{Properties, PropertiesSource, Section};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use toml::value::{Table, Value};
+
+pub static EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// The sections of an `.editorconfig` file
+struct EditorConfigFile {
+    /// The directory containing the file. Section globs are matched relative to this directory
+    directory: PathBuf,
+    /// Whether the file is marked with `root = true`, so files in parent directories should not be read
+    is_root: bool,
+    sections: Vec<Section>,
+}
+
+/// Reads `.editorconfig` files, caching the file found in each directory so that it is only parsed once
+#[derive(Default)]
+pub struct EditorConfigCache {
+    directories: HashMap<PathBuf, Option<Rc<EditorConfigFile>>>,
+}
+
+impl EditorConfigCache {
+    /// Clears all cached `.editorconfig` files, so that they are re-read when next needed
+    pub fn clear(&mut self) {
+        self.directories.clear();
+    }
+
+    /// Reads the `.editorconfig` file within the directory, if there is one
+    fn file_in(&mut self, directory: &Path) -> Result<Option<Rc<EditorConfigFile>>> {
+        if !self.directories.contains_key(directory) {
+            let file_path = directory.join(EDITORCONFIG_FILE_NAME);
+            // Any file which cannot be opened is skipped, in the same way as `ec4rs::properties_of`
+            let file = match ec4rs::ConfigFile::open(&file_path) {
+                Ok(mut file) => {
+                    let sections = (&mut file)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|error| file.add_error_context(error))
+                        .with_context(|| format!("Failed to read {}", file_path.display()))?;
+                    Some(Rc::new(EditorConfigFile {
+                        directory: directory.to_path_buf(),
+                        is_root: file.reader.is_root,
+                        sections,
+                    }))
+                }
+                Err(_) => None,
+            };
+            self.directories.insert(directory.to_path_buf(), file);
+        }
+
+        Ok(self.directories[directory].clone())
+    }
+
+    /// Reads the properties of any `.editorconfig` sections matching the file at the given (absolute) path,
+    /// converting them into the equivalent configuration options
+    pub fn read_options(&mut self, path: &Path) -> Result<Table> {
+        let mut files = Vec::new();
+        for directory in path.ancestors().skip(1) {
+            if let Some(file) = self.file_in(directory)? {
+                let is_root = file.is_root;
+                files.push(file);
+                if is_root {
+                    break;
+                }
+            }
+        }
+
+        // Files nearer to the path take precedence, so are applied last
+        let mut properties = Properties::new();
+        for file in files.iter().rev() {
+            let relative_path = path.strip_prefix(&file.directory).unwrap_or(path);
+            for section in &file.sections {
+                // Applying a section cannot fail
+                let _ = section.apply_to(&mut properties, relative_path);
+            }
+        }
+        properties.use_fallbacks();
+
+        Ok(options_from_properties(&properties))
+    }
+}
+
+/// Converts the EditorConfig properties into the equivalent configuration options
+fn options_from_properties(properties: &Properties) -> Table {
+    let mut options = Table::new();
+
+    match properties.get::<IndentStyle>() {
+        Ok(IndentStyle::Tabs) => {
+            options.insert("indent_type".to_owned(), Value::String("Tabs".to_owned()));
+        }
+        Ok(IndentStyle::Spaces) => {
+            options.insert("indent_type".to_owned(), Value::String("Spaces".to_owned()));
+        }
+        Err(_) => (),
+    }
+
+    if let Ok(IndentSize::Value(indent_size)) = properties.get::<IndentSize>() {
+        options.insert(
+            "indent_width".to_owned(),
+            Value::Integer(indent_size as i64),
+        );
+    }
+
+    // Carriage return line endings are not supported, so are ignored
+    match properties.get::<EndOfLine>() {
+        Ok(EndOfLine::Lf) => {
+            options.insert("line_endings".to_owned(), Value::String("Unix".to_owned()));
+        }
+        Ok(EndOfLine::CrLf) => {
+            options.insert(
+                "line_endings".to_owned(),
+                Value::String("Windows".to_owned()),
+            );
+        }
+        Ok(EndOfLine::Cr) | Err(_) => (),
+    }
+
+    if let Ok(MaxLineLen::Value(max_line_length)) = properties.get::<MaxLineLen>() {
+        options.insert(
+            "column_width".to_owned(),
+            Value::Integer(max_line_length as i64),
+        );
+    }
+
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Reads the options for a Lua file, using an `.editorconfig` with the given properties in a `[*.lua]` section
+    fn read_lua_options(properties: &str) -> Table {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(".editorconfig"),
+            format!("root = true\n\n[*.lua]\n{}", properties),
+        )
+        .unwrap();
+
+        EditorConfigCache::default()
+            .read_options(&dir.path().join("init.lua"))
+            .unwrap()
+    }
+
+    fn table(options: &[(&str, Value)]) -> Table {
+        options
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_indent_style() {
+        assert_eq!(
+            read_lua_options("indent_style = tab\n"),
+            table(&[("indent_type", Value::String("Tabs".to_owned()))])
+        );
+        assert_eq!(
+            read_lua_options("indent_style = space\nindent_size = 2\n"),
+            table(&[
+                ("indent_type", Value::String("Spaces".to_owned())),
+                ("indent_width", Value::Integer(2)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_indent_size_fallbacks() {
+        // `indent_size = tab` uses `tab_width`
+        assert_eq!(
+            read_lua_options("indent_size = tab\ntab_width = 8\n"),
+            table(&[("indent_width", Value::Integer(8))])
+        );
+        // `tab_width` is used when `indent_size` is not set
+        assert_eq!(
+            read_lua_options("tab_width = 3\n"),
+            table(&[("indent_width", Value::Integer(3))])
+        );
+        // Without `tab_width`, `indent_size = tab` provides no width
+        assert_eq!(read_lua_options("indent_size = tab\n"), Table::new());
+    }
+
+    #[test]
+    fn test_end_of_line() {
+        assert_eq!(
+            read_lua_options("end_of_line = lf\n"),
+            table(&[("line_endings", Value::String("Unix".to_owned()))])
+        );
+        assert_eq!(
+            read_lua_options("end_of_line = crlf\n"),
+            table(&[("line_endings", Value::String("Windows".to_owned()))])
+        );
+        // Carriage return line endings are not supported, so are ignored
+        assert_eq!(read_lua_options("end_of_line = cr\n"), Table::new());
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        assert_eq!(
+            read_lua_options("max_line_length = 100\n"),
+            table(&[("column_width", Value::Integer(100))])
+        );
+        assert_eq!(read_lua_options("max_line_length = off\n"), Table::new());
+    }
+
+    #[test]
+    fn test_unmatched_section() {
+        let dir = TempDir::new().unwrap();
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "root = true\n\n[*.py]\nindent_style = space\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            EditorConfigCache::default()
+                .read_options(&dir.path().join("init.lua"))
+                .unwrap(),
+            Table::new()
+        );
+    }
+
+    #[test]
+    fn test_nearer_files_take_precedence() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("project/src")).unwrap();
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "[*]\nmax_line_length = 80\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("project/.editorconfig"),
+            "root = true\n\n[*]\nindent_style = tab\nmax_line_length = 100\n\n[src/*.lua]\nindent_style = space\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("project/src/.editorconfig"),
+            "[*.lua]\nindent_size = 2\n",
+        )
+        .unwrap();
+
+        // Files above the root file are not read, and section globs are relative to the file's directory
+        assert_eq!(
+            EditorConfigCache::default()
+                .read_options(&dir.path().join("project/src/init.lua"))
+                .unwrap(),
+            table(&[
+                ("indent_type", Value::String("Spaces".to_owned())),
+                ("indent_width", Value::Integer(2)),
+                ("column_width", Value::Integer(100)),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_files_are_cached() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("init.lua");
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "root = true\n\n[*.lua]\nindent_style = tab\n",
+        )
+        .unwrap();
+
+        let mut cache = EditorConfigCache::default();
+        let options = cache.read_options(&path).unwrap();
+
+        // Changes are not seen until the cache is cleared
+        fs::write(
+            dir.path().join(".editorconfig"),
+            "root = true\n\n[*.lua]\nindent_style = space\n",
+        )
+        .unwrap();
+        assert_eq!(cache.read_options(&path).unwrap(), options);
+
+        cache.clear();
+        assert_eq!(
+            cache.read_options(&path).unwrap(),
+            table(&[("indent_type", Value::String("Spaces".to_owned()))])
+        );
+    }
+}
@@ -0,0 +1,294 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::opt::{LineRange, Opt};
+
+/// The set of changes to compare against when only formatting changed lines
+pub enum ChangeSource {
+    /// Changes in the working tree since the given revision
+    Since(String),
+    /// Changes staged in the index, compared against `HEAD`
+    Staged,
+}
+
+impl ChangeSource {
+    /// Determines the change source requested through the options, if any
+    pub fn from_opt(opt: &Opt) -> Option<Self> {
+        match &opt.changed_since {
+            Some(revision) => Some(ChangeSource::Since(revision.to_owned())),
+            None if opt.staged => Some(ChangeSource::Staged),
+            None => None,
+        }
+    }
+}
+
+/// Runs a git command in the given directory, returning its stdout
+fn run_git(directory: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .output()
+        .context("Failed to run git, is it installed?")?;
+
+    if !output.status.success() {
+        bail!(
+            "error: git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).context("git output was not valid UTF-8")
+}
+
+/// Parses the new side of a hunk header, such as `@@ -12,3 +14,2 @@`, into the range of lines it covers.
+/// Returns `None` if the hunk only removes lines.
+fn parse_hunk_header(line: &str) -> Option<LineRange> {
+    let new_side = line.split_whitespace().nth(2)?.strip_prefix('+')?;
+    let (start, count) = match new_side.split_once(',') {
+        Some((start, count)) => (start.parse::<usize>().ok()?, count.parse::<usize>().ok()?),
+        None => (new_side.parse::<usize>().ok()?, 1),
+    };
+
+    if count == 0 {
+        None
+    } else {
+        Some(LineRange {
+            start,
+            end: start + count - 1,
+        })
+    }
+}
+
+/// Unquotes a path quoted by git, which uses C-style escapes for unusual characters such as `"` or tabs
+fn unquote_path(quoted: &str) -> Option<String> {
+    let mut bytes = quoted.strip_prefix('"')?.strip_suffix('"')?.bytes();
+    let mut path = Vec::new();
+
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            path.push(byte);
+            continue;
+        }
+
+        let escaped = bytes.next()?;
+        path.push(match escaped {
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b't' => b'\t',
+            b'n' => b'\n',
+            b'v' => 0x0b,
+            b'f' => 0x0c,
+            b'r' => b'\r',
+            // Bytes outside of ASCII are written as three octal digits
+            b'0'..=b'7' => {
+                let digits = [escaped, bytes.next()?, bytes.next()?];
+                u8::from_str_radix(std::str::from_utf8(&digits).ok()?, 8).ok()?
+            }
+            _ => escaped,
+        });
+    }
+
+    String::from_utf8(path).ok()
+}
+
+/// Parses the path of the new file from a `+++ b/<path>` header line of a diff.
+/// Returns `None` for deleted files, which have no new path.
+fn parse_new_path(line: &str) -> Option<String> {
+    // git appends a tab to the header when the path contains a space
+    let path = line.strip_prefix("+++ ")?.trim_end_matches('\t');
+    let path = if path.starts_with('"') {
+        unquote_path(path)?
+    } else {
+        path.to_owned()
+    };
+
+    path.strip_prefix("b/").map(str::to_owned)
+}
+
+/// Parses the output of `git diff --unified=0` into the ranges of lines changed within each file,
+/// along with the path of the file relative to the repository root. Files with no added lines are skipped.
+fn parse_diff(diff: &str) -> Vec<(String, Vec<LineRange>)> {
+    let mut files: Vec<(String, Vec<LineRange>)> = Vec::new();
+    // Whether we are within the header of a file's diff, before its first hunk. Hunk contents always start with
+    // `+`, `-` or a space, so a changed line such as `++ x` cannot be mistaken for a header outside of it
+    let mut in_header = false;
+    let mut has_new_path = false;
+
+    for line in diff.lines() {
+        if line.starts_with("diff ") {
+            in_header = true;
+            has_new_path = false;
+        } else if in_header && line.starts_with("+++ ") {
+            if let Some(path) = parse_new_path(line) {
+                files.push((path, Vec::new()));
+                has_new_path = true;
+            }
+        } else if line.starts_with("@@ ") {
+            in_header = false;
+            if let (true, Some((_, ranges)), Some(range)) =
+                (has_new_path, files.last_mut(), parse_hunk_header(line))
+            {
+                ranges.push(range);
+            }
+        }
+    }
+
+    files.retain(|(_, ranges)| !ranges.is_empty());
+    files
+}
+
+/// Splits the NUL separated list of paths output by git when using `-z`
+fn parse_paths(output: &str) -> Vec<&str> {
+    output.split('\0').filter(|path| !path.is_empty()).collect()
+}
+
+/// Computes the lines changed in each file of the current git repository, keyed by the canonical path of the file.
+/// Line ranges for each file are in ascending order, as given by git.
+/// When comparing against a revision, untracked files which are not ignored are treated as entirely changed.
+pub fn changed_lines(source: &ChangeSource) -> Result<HashMap<PathBuf, Vec<LineRange>>> {
+    let current_dir = std::env::current_dir().context("Could not find current directory")?;
+    let root = run_git(&current_dir, &["rev-parse", "--show-toplevel"])
+        .context("Could not find the root of the git repository")?;
+    let root = Path::new(root.trim())
+        .canonicalize()
+        .context("Could not find the root of the git repository")?;
+
+    // Fix the prefixes of the file paths, in case the user has configured git to use different ones
+    let mut diff_args = vec![
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "--unified=0",
+        "--no-color",
+        "--no-ext-diff",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+        // Deleted files have no lines left to format
+        "--diff-filter=d",
+    ];
+    match source {
+        ChangeSource::Since(revision) => diff_args.push(revision),
+        ChangeSource::Staged => diff_args.push("--cached"),
+    }
+    diff_args.push("--");
+
+    let mut changed_lines = HashMap::new();
+    for (path, ranges) in parse_diff(&run_git(&root, &diff_args)?) {
+        changed_lines.insert(root.join(path), ranges);
+    }
+
+    if let ChangeSource::Since(_) = source {
+        let untracked = run_git(&root, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+        for name in parse_paths(&untracked) {
+            changed_lines.insert(
+                root.join(name),
+                vec![LineRange {
+                    start: 1,
+                    end: usize::MAX,
+                }],
+            );
+        }
+    }
+
+    Ok(changed_lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(ranges: &[LineRange]) -> Vec<(usize, usize)> {
+        ranges
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    fn hunk(header: &str) -> Option<(usize, usize)> {
+        parse_hunk_header(header).map(|range| (range.start, range.end))
+    }
+
+    fn files(diff: &str) -> Vec<(String, Vec<(usize, usize)>)> {
+        parse_diff(diff)
+            .into_iter()
+            .map(|(path, file_ranges)| (path, ranges(&file_ranges)))
+            .collect()
+    }
+
+    #[test]
+    fn test_hunk_with_count() {
+        assert_eq!(hunk("@@ -12,3 +14,2 @@ local x = 1"), Some((14, 15)));
+    }
+
+    #[test]
+    fn test_hunk_missing_count() {
+        assert_eq!(hunk("@@ -3 +4 @@"), Some((4, 4)));
+    }
+
+    #[test]
+    fn test_deletion_only_hunk() {
+        assert_eq!(hunk("@@ -5,2 +4,0 @@"), None);
+
+        let diff = "diff --git a/file.lua b/file.lua\n--- a/file.lua\n+++ b/file.lua\n@@ -5,2 +4,0 @@\n-local x = 1\n-local y = 2\n";
+        assert_eq!(files(diff), vec![]);
+    }
+
+    #[test]
+    fn test_new_file_diff() {
+        let diff = "diff --git a/new.lua b/new.lua\nnew file mode 100644\n--- /dev/null\n+++ b/new.lua\n@@ -0,0 +1,2 @@\n+local x = 1\n+local y = 2\n";
+        assert_eq!(files(diff), vec![("new.lua".to_owned(), vec![(1, 2)])]);
+    }
+
+    #[test]
+    fn test_deleted_file_diff() {
+        let diff = "diff --git a/old.lua b/old.lua\ndeleted file mode 100644\n--- a/old.lua\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-local x = 1\n-local y = 2\n";
+        assert_eq!(files(diff), vec![]);
+    }
+
+    #[test]
+    fn test_multiple_files() {
+        let diff = "diff --git a/a.lua b/a.lua\n--- a/a.lua\n+++ b/a.lua\n@@ -1 +1 @@\n-local x = 1\n+local x = 2\n@@ -10,0 +11,3 @@\n+a()\n+b()\n+c()\n\
+                    diff --git a/dir/b.lua b/dir/b.lua\n--- a/dir/b.lua\n+++ b/dir/b.lua\n@@ -4,2 +4,2 @@\n-x\n-y\n+x\n+y\n";
+        assert_eq!(
+            files(diff),
+            vec![
+                ("a.lua".to_owned(), vec![(1, 1), (11, 13)]),
+                ("dir/b.lua".to_owned(), vec![(4, 5)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changed_lines_are_not_headers() {
+        let diff = "diff --git a/file.lua b/file.lua\n--- a/file.lua\n+++ b/file.lua\n@@ -1,2 +1,2 @@\n--- comment\n-@@ -1 +1 @@\n+++ b/other.lua\n+local x = 1\n";
+        assert_eq!(files(diff), vec![("file.lua".to_owned(), vec![(1, 2)])]);
+    }
+
+    #[test]
+    fn test_paths_with_spaces() {
+        let diff = "diff --git a/sp ace.lua b/sp ace.lua\n--- a/sp ace.lua\t\n+++ b/sp ace.lua\t\n@@ -1 +1 @@\n-x\n+y\n";
+        assert_eq!(files(diff), vec![("sp ace.lua".to_owned(), vec![(1, 1)])]);
+
+        assert_eq!(
+            parse_paths("sp ace.lua\0dir/other file.lua\0"),
+            vec!["sp ace.lua", "dir/other file.lua"]
+        );
+    }
+
+    #[test]
+    fn test_quoted_paths() {
+        assert_eq!(
+            parse_new_path("+++ \"b/tab\\there \\\"quoted\\\".lua\"\t"),
+            Some("tab\there \"quoted\".lua".to_owned())
+        );
+        assert_eq!(
+            parse_new_path("+++ \"b/new\\nline\\303\\251.lua\""),
+            Some("new\nline\u{e9}.lua".to_owned())
+        );
+        assert_eq!(parse_new_path("+++ /dev/null"), None);
+    }
+}
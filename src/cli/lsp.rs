@@ -0,0 +1,397 @@
+use anyhow::{Context, Result};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidChangeWatchedFiles, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as _,
+    },
+    request::{Formatting, OnTypeFormatting, RangeFormatting, Request as _},
+    DidChangeTextDocumentParams, DidChangeWatchedFilesParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentOnTypeFormattingOptions,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, InitializeParams, OneOf,
+    Position, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use stylua_lib::{format_code_edits, ColumnEncoding, Error, OutputVerification, Range};
+
+use crate::{
+    config::{self, ConfigResolver, ResolvedConfig},
+    editorconfig,
+    opt::Opt,
+};
+
+/// State held by the language server for the lifetime of the connection
+struct Server {
+    opt: Arc<Opt>,
+    /// The root directories of each workspace folder opened by the client
+    workspace_folders: Vec<PathBuf>,
+    /// The contents of all documents currently open in the client
+    documents: HashMap<Url, String>,
+    /// Resolves the configuration for each document, caching configuration files so we do not re-read them on every request
+    configs: ConfigResolver,
+}
+
+/// Converts a byte offset in the text into an LSP position
+fn offset_to_position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let character = before[line_start..].encode_utf16().count();
+
+    Position::new(line as u32, character as u32)
+}
+
+/// Converts an LSP range within the text into a formatting range
+fn to_format_range(text: &str, range: lsp_types::Range) -> Range {
+    // LSP positions are zero-based, whilst formatting ranges are 1-indexed
+    Range::from_positions(
+        text,
+        (
+            range.start.line as usize + 1,
+            range.start.character as usize + 1,
+        ),
+        (
+            range.end.line as usize + 1,
+            range.end.character as usize + 1,
+        ),
+        ColumnEncoding::Utf16,
+    )
+}
+
+impl Server {
+    fn new(opt: Opt, params: InitializeParams) -> Result<Self> {
+        let mut workspace_folders: Vec<PathBuf> = params
+            .workspace_folders
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|folder| folder.uri.to_file_path().ok())
+            .collect();
+
+        if workspace_folders.is_empty() {
+            if let Some(root) = params.root_uri.and_then(|uri| uri.to_file_path().ok()) {
+                workspace_folders.push(root);
+            }
+        }
+
+        let opt = Arc::new(opt);
+        Ok(Self {
+            configs: ConfigResolver::new(opt.clone())?,
+            opt,
+            workspace_folders,
+            documents: HashMap::new(),
+        })
+    }
+
+    /// Finds the directory to stop the configuration search at for the given document.
+    /// This is the innermost workspace folder containing the document, falling back to the document's own directory.
+    fn config_directory(&self, path: &Path) -> Option<PathBuf> {
+        self.workspace_folders
+            .iter()
+            .filter(|folder| path.starts_with(folder))
+            .max_by_key(|folder| folder.components().count())
+            .cloned()
+            .or_else(|| path.parent().map(Path::to_path_buf))
+    }
+
+    /// Resolves the configuration to use for the given document, in the same way as the command line.
+    /// The nearest configuration file to the document within its workspace folder is used, with any overrides applied.
+    fn config_for(&mut self, path: Option<&Path>) -> Result<ResolvedConfig> {
+        match path {
+            Some(path) => {
+                let root = self
+                    .config_directory(path)
+                    .unwrap_or_else(|| path.to_path_buf());
+                self.configs.config_for_file_within(path, &root)
+            }
+            None => self.configs.config_for_stdin(),
+        }
+    }
+
+    /// Formats the given document, converting the resultant byte edits into LSP text edits
+    fn format(&mut self, uri: &Url, range: Option<Range>) -> Result<Vec<TextEdit>> {
+        let path = uri.to_file_path().ok();
+        let config = self.config_for(path.as_deref())?;
+        let text = self
+            .documents
+            .get(uri)
+            .with_context(|| format!("Document {} is not open", uri))?;
+
+        let verify_output = if self.opt.verify {
+            OutputVerification::Full
+        } else {
+            OutputVerification::None
+        };
+
+        let edits = match config.format_with(path.as_deref(), |config| {
+            format_code_edits(text, config, range, verify_output)
+        }) {
+            Ok(edits) => edits,
+            // Range and on-type formatting are requested whilst the code is being edited, so it is often incomplete.
+            // Rather than reporting an error on every keystroke, nothing is formatted until the code parses
+            Err(Error::ParseError { .. }) if range.is_some() => Vec::new(),
+            Err(error) => {
+                return Err(error).with_context(|| format!("Could not format {}", uri));
+            }
+        };
+
+        Ok(edits
+            .into_iter()
+            .map(|(span, new_text)| TextEdit {
+                range: lsp_types::Range::new(
+                    offset_to_position(text, span.start),
+                    offset_to_position(text, span.end),
+                ),
+                new_text,
+            })
+            .collect())
+    }
+
+    fn handle_request(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+        match self.dispatch_request(request) {
+            Ok(response) => response,
+            Err(error) => {
+                Response::new_err(id, ErrorCode::RequestFailed as i32, format!("{:#}", error))
+            }
+        }
+    }
+
+    fn dispatch_request(&mut self, request: Request) -> Result<Response> {
+        let id = request.id;
+        match request.method.as_str() {
+            Formatting::METHOD => {
+                let params: DocumentFormattingParams = serde_json::from_value(request.params)?;
+                let edits = self.format(&params.text_document.uri, None)?;
+                Ok(Response::new_ok(id, edits))
+            }
+            RangeFormatting::METHOD => {
+                let params: DocumentRangeFormattingParams = serde_json::from_value(request.params)?;
+                let range = self.document_range(&params.text_document.uri, params.range)?;
+                let edits = self.format(&params.text_document.uri, Some(range))?;
+                Ok(Response::new_ok(id, edits))
+            }
+            OnTypeFormatting::METHOD => {
+                let params: DocumentOnTypeFormattingParams =
+                    serde_json::from_value(request.params)?;
+                let uri = params.text_document_position.text_document.uri;
+                let position = params.text_document_position.position;
+
+                // A newline has just been typed, so format the statement on the line that was just completed
+                let start = Position::new(position.line.saturating_sub(1), 0);
+                let range = self.document_range(&uri, lsp_types::Range::new(start, position))?;
+                let edits = self.format(&uri, Some(range))?;
+                Ok(Response::new_ok(id, edits))
+            }
+            _ => Ok(Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unhandled method {}", request.method),
+            )),
+        }
+    }
+
+    /// Converts an LSP range within a document into a formatting range
+    fn document_range(&self, uri: &Url, range: lsp_types::Range) -> Result<Range> {
+        let text = self
+            .documents
+            .get(uri)
+            .with_context(|| format!("Document {} is not open", uri))?;
+
+        Ok(to_format_range(text, range))
+    }
+
+    fn handle_notification(&mut self, notification: Notification) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents
+                    .insert(params.text_document.uri, params.text_document.text);
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                // We only advertise full document sync, so the last change holds the whole document
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(params.text_document.uri, change.text);
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+            }
+            DidChangeWatchedFiles::METHOD => {
+                let params: DidChangeWatchedFilesParams =
+                    serde_json::from_value(notification.params)?;
+                // A new configuration file may now be the nearest to some documents, so any file with the name of a
+                // configuration or `.editorconfig` file is treated as a change, along with any file which cached
+                // configuration was read from
+                let config_changed = params
+                    .changes
+                    .iter()
+                    .filter_map(|change| change.uri.to_file_path().ok())
+                    .any(|path| {
+                        self.configs.is_config_source(&path)
+                            || matches!(
+                                path.file_name().and_then(|name| name.to_str()),
+                                Some(name) if config::CONFIG_FILE_NAME.contains(&name)
+                                    || name == editorconfig::EDITORCONFIG_FILE_NAME
+                            )
+                    });
+
+                // Configuration was modified, so it needs to be re-read on the next request
+                if config_changed {
+                    self.configs.clear();
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs StyLua as a language server over stdio, until the client requests a shutdown
+pub fn run(opt: Opt) -> Result<i32> {
+    // stdout is used as the transport for the protocol, so we cannot print any verbose output
+    let opt = Opt {
+        verbose: false,
+        ..opt
+    };
+
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+            first_trigger_character: String::from("\n"),
+            more_trigger_character: None,
+        }),
+        ..ServerCapabilities::default()
+    };
+
+    let params = connection
+        .initialize(serde_json::to_value(capabilities)?)
+        .context("Failed to initialize language server")?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    let mut server = Server::new(opt, params)?;
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    break;
+                }
+
+                let response = server.handle_request(request);
+                connection.sender.send(Message::Response(response))?;
+            }
+            Message::Notification(notification) => {
+                if let Err(error) = server.handle_notification(notification) {
+                    eprintln!("{:#}", error);
+                }
+            }
+            Message::Response(_) => (),
+        }
+    }
+
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    #[test]
+    fn test_incomplete_code_is_not_range_formatted() {
+        let opt = Opt::from_iter(&["stylua", "--lsp"]);
+        let mut server = Server::new(opt, InitializeParams::default()).unwrap();
+
+        let uri = Url::parse("untitled:Untitled-1").unwrap();
+        let text = "local x = {\n";
+        server.documents.insert(uri.clone(), text.to_owned());
+
+        // Range and on-type formatting return no edits, rather than an error, whilst the code does not parse
+        let range = to_format_range(
+            text,
+            lsp_types::Range::new(Position::new(0, 0), Position::new(1, 0)),
+        );
+        assert!(server.format(&uri, Some(range)).unwrap().is_empty());
+
+        // Formatting the whole document still reports the error
+        assert!(server.format(&uri, None).is_err());
+    }
+
+    #[test]
+    fn test_offset_to_position() {
+        let text = "local x = 1\nlocal y = 2\n";
+        assert_eq!(offset_to_position(text, 0), Position::new(0, 0));
+        assert_eq!(offset_to_position(text, 6), Position::new(0, 6));
+        assert_eq!(offset_to_position(text, 12), Position::new(1, 0));
+        assert_eq!(offset_to_position(text, text.len()), Position::new(2, 0));
+    }
+
+    #[test]
+    fn test_offset_to_position_utf16() {
+        // `€` is three bytes in UTF-8, but a single UTF-16 code unit, whilst `𝄞` is four bytes and two code units
+        let text = "local s = \"€𝄞\" local x = 1";
+        let offset = text.find(" local x").unwrap();
+        assert_eq!(offset_to_position(text, offset), Position::new(0, 15));
+    }
+
+    #[test]
+    fn test_offset_to_position_crlf() {
+        let text = "local x = 1\r\nlocal y = 2\r\n";
+        assert_eq!(offset_to_position(text, 11), Position::new(0, 11));
+        assert_eq!(offset_to_position(text, 13), Position::new(1, 0));
+        assert_eq!(offset_to_position(text, 19), Position::new(1, 6));
+    }
+
+    fn assert_format_range(text: &str, range: lsp_types::Range, start: usize, end: usize) {
+        assert_eq!(
+            format!("{:?}", to_format_range(text, range)),
+            format!("{:?}", Range::from_values(Some(start), Some(end)))
+        );
+    }
+
+    #[test]
+    fn test_document_range_utf16() {
+        let text = "local s = \"€\"\nlocal x = 1\n";
+        // The closing quote is after 12 UTF-16 code units, but 14 bytes
+        assert_format_range(
+            text,
+            lsp_types::Range::new(Position::new(0, 12), Position::new(1, 5)),
+            14,
+            21,
+        );
+    }
+
+    #[test]
+    fn test_document_range_crlf() {
+        let text = "local x = 1\r\nlocal y = 2\r\n";
+        assert_format_range(
+            text,
+            lsp_types::Range::new(Position::new(1, 0), Position::new(1, 11)),
+            13,
+            24,
+        );
+        // Positions past the end of a line are clamped to before its line ending
+        assert_format_range(
+            text,
+            lsp_types::Range::new(Position::new(0, 0), Position::new(0, 40)),
+            0,
+            11,
+        );
+    }
+}
@@ -9,9 +9,12 @@ use std::time::Instant;
 use structopt::StructOpt;
 use threadpool::ThreadPool;
 
-use stylua_lib::{format_code, Config, OutputVerification, Range};
+use stylua_lib::{format_code, format_code_ranges, Config, LuaVersion, OutputVerification, Range};
 
 mod config;
+mod editorconfig;
+mod git;
+mod lsp;
 mod opt;
 mod output_diff;
 
@@ -48,10 +51,39 @@ enum FormatResult {
     Diff(Vec<u8>),
 }
 
+/// Determines the range to format within the given contents.
+/// A line range is converted into byte offsets here, as it depends on the contents being formatted.
+fn resolve_range(contents: &str, range: Option<Range>, opt: &opt::Opt) -> Option<Range> {
+    match opt.line_range {
+        Some(line_range) => Some(Range::from_lines(
+            contents,
+            line_range.start,
+            line_range.end,
+        )),
+        None => range,
+    }
+}
+
+/// Formats each of the changed line ranges within the contents, in a single pass
+fn format_changed_lines(
+    contents: &str,
+    config: Config,
+    changed_lines: &[opt::LineRange],
+    verify_output: OutputVerification,
+) -> Result<String, stylua_lib::Error> {
+    let ranges: Vec<Range> = changed_lines
+        .iter()
+        .map(|line_range| Range::from_lines(contents, line_range.start, line_range.end))
+        .collect();
+
+    format_code_ranges(contents, config, &ranges, verify_output)
+}
+
 fn format_file(
     path: &Path,
-    config: Config,
+    config: config::ResolvedConfig,
     range: Option<Range>,
+    changed_lines: Option<&[opt::LineRange]>,
     opt: &opt::Opt,
     verify_output: OutputVerification,
 ) -> Result<FormatResult> {
@@ -59,7 +91,18 @@ fn format_file(
         fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
 
     let before_formatting = Instant::now();
-    let formatted_contents = format_code(&contents, config, range, verify_output)
+    let formatted_contents = config
+        .format_with(Some(path), |config| match changed_lines {
+            Some(changed_lines) => {
+                format_changed_lines(&contents, config, changed_lines, verify_output)
+            }
+            None => format_code(
+                &contents,
+                config,
+                resolve_range(&contents, range, opt),
+                verify_output,
+            ),
+        })
         .with_context(|| format!("Could not format file {}", path.display()))?;
     let after_formatting = Instant::now();
 
@@ -95,13 +138,17 @@ fn format_file(
 /// Used when input has been provided to stdin
 fn format_string(
     input: String,
-    config: Config,
+    config: config::ResolvedConfig,
     range: Option<Range>,
     opt: &opt::Opt,
     verify_output: OutputVerification,
 ) -> Result<FormatResult> {
-    let formatted_contents =
-        format_code(&input, config, range, verify_output).context("Failed to format from stdin")?;
+    let range = resolve_range(&input, range, opt);
+    let formatted_contents = config
+        .format_with(opt.stdin_filepath.as_deref(), |config| {
+            format_code(&input, config, range, verify_output)
+        })
+        .context("Failed to format from stdin")?;
 
     if opt.check {
         let diff = output_diff::output_diff(
@@ -124,18 +171,40 @@ fn format_string(
     }
 }
 
-fn format(opt: opt::Opt) -> Result<i32> {
+fn format(mut opt: opt::Opt) -> Result<i32> {
+    // Determine the lines changed in the git repository, if we only want to format changed lines
+    let changed_lines = match git::ChangeSource::from_opt(&opt) {
+        Some(source) => {
+            let changed_lines = git::changed_lines(&source)?;
+
+            // Default to formatting all changed Lua and Luau files if none were provided
+            if opt.files.is_empty() {
+                opt.files = changed_lines
+                    .keys()
+                    .filter(|path| {
+                        path.extension().map_or(false, |extension| {
+                            extension == "lua"
+                                || (extension == "luau" && LuaVersion::Luau.is_supported())
+                        })
+                    })
+                    .cloned()
+                    .collect();
+
+                if opt.files.is_empty() {
+                    verbose_println!(opt.verbose, "no changed files to format");
+                    return Ok(0);
+                }
+            }
+
+            Some(Arc::new(changed_lines))
+        }
+        None => None,
+    };
+
     if opt.files.is_empty() {
         bail!("error: no files provided");
     }
 
-    // Load the configuration
-    let config = config::load_config(&opt)?;
-
-    // Handle any configuration overrides provided by options
-    let config = config::load_overrides(config, &opt);
-    verbose_println!(opt.verbose, "config: {:#?}", config);
-
     // Create range if provided
     let range = if opt.range_start.is_some() || opt.range_end.is_some() {
         Some(Range::from_values(opt.range_start, opt.range_end))
@@ -198,6 +267,9 @@ fn format(opt: opt::Opt) -> Result<i32> {
     let error_code = Arc::new(AtomicI32::new(0));
     let opt = Arc::new(opt);
 
+    // Configuration is resolved for each file, from the configuration file nearest to it
+    let mut config_resolver = config::ConfigResolver::new(opt.clone())?;
+
     // Create a thread to handle the formatting output
     let read_error_code = error_code.clone();
     pool.execute(move || {
@@ -239,6 +311,14 @@ fn format(opt: opt::Opt) -> Result<i32> {
         match result {
             Ok(entry) => {
                 if entry.is_stdin() {
+                    let config = match config_resolver.config_for_stdin() {
+                        Ok(config) => config,
+                        Err(error) => {
+                            error!(error_code, 2, "{:#}", error);
+                            continue;
+                        }
+                    };
+
                     let tx = tx.clone();
                     let opt = opt.clone();
 
@@ -262,17 +342,53 @@ fn format(opt: opt::Opt) -> Result<i32> {
                         // We should ignore the glob check if the path provided was explicitly given to the CLI
                         if use_default_glob && !opt.files.iter().any(|p| path == *p) {
                             lazy_static::lazy_static! {
-                                static ref DEFAULT_GLOB: globset::GlobMatcher = globset::Glob::new("**/*.lua").expect("cannot create default glob").compile_matcher();
+                                // Luau files are only matched if this build of StyLua can parse them
+                                static ref DEFAULT_GLOB: globset::GlobMatcher = globset::Glob::new(if LuaVersion::Luau.is_supported() { "**/*.{lua,luau}" } else { "**/*.lua" }).expect("cannot create default glob").compile_matcher();
                             }
                             if !DEFAULT_GLOB.is_match(&path) {
                                 continue;
                             }
                         }
 
+                        // When only formatting changed lines, skip any file which has not changed
+                        let changed_lines = changed_lines.clone();
+                        if let Some(changed_lines) = &changed_lines {
+                            let has_changed = path
+                                .canonicalize()
+                                .map_or(false, |path| changed_lines.contains_key(&path));
+                            if !has_changed {
+                                continue;
+                            }
+                        }
+
+                        let config = match config_resolver.config_for_file(&path) {
+                            Ok(config) => config,
+                            Err(error) => {
+                                error!(error_code, 2, "{:#}", error);
+                                continue;
+                            }
+                        };
+
                         let tx = tx.clone();
                         pool.execute(move || {
-                            tx.send(format_file(&path, config, range, &opt, verify_output))
-                                .unwrap()
+                            let file_changed_lines =
+                                changed_lines.as_ref().and_then(|changed_lines| {
+                                    path.canonicalize()
+                                        .ok()
+                                        .and_then(|canonical_path| {
+                                            changed_lines.get(&canonical_path)
+                                        })
+                                        .map(|ranges| ranges.as_slice())
+                                });
+                            tx.send(format_file(
+                                &path,
+                                config,
+                                range,
+                                file_changed_lines,
+                                &opt,
+                                verify_output,
+                            ))
+                            .unwrap()
                         });
                     }
                 }
@@ -301,7 +417,9 @@ fn format(opt: opt::Opt) -> Result<i32> {
 fn main() {
     let opt = opt::Opt::from_args();
 
-    let exit_code = match format(opt) {
+    let result = if opt.lsp { lsp::run(opt) } else { format(opt) };
+
+    let exit_code = match result {
         Ok(code) => code,
         Err(e) => {
             eprintln!("{:#}", e);
@@ -1,6 +1,7 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::{clap::arg_enum, StructOpt};
-use stylua_lib::{IndentType, LineEndings, QuoteStyle};
+use stylua_lib::{IndentType, LineEndings, LuaVersion, QuoteStyle};
 
 lazy_static::lazy_static! {
     static ref NUM_CPUS: String = num_cpus::get().to_string();
@@ -26,6 +27,12 @@ pub struct Opt {
     #[structopt(short, long)]
     pub search_parent_directories: bool,
 
+    /// Disables reading `.editorconfig` files.
+    /// By default, the `indent_style`, `indent_size`, `end_of_line` and `max_line_length` properties of any
+    /// `.editorconfig` sections matching a file are used, unless set in the configuration file.
+    #[structopt(long)]
+    pub no_editorconfig: bool,
+
     /// Runs in 'check' mode.
     /// Exits with 0 if all formatting is OK,
     /// Exits with 1 if the formatting is incorrect.
@@ -65,6 +72,28 @@ pub struct Opt {
     #[structopt(long)]
     pub range_end: Option<usize>,
 
+    /// A range of lines to format files, given as `start:end` (both inclusive, 1-indexed).
+    /// Either side can be omitted to extend the range to the start or end of the file, and a single line can be given as `start`.
+    /// Cannot be used alongside `--range-start` or `--range-end`.
+    #[structopt(long, conflicts_with_all = &["range-start", "range-end"])]
+    pub line_range: Option<LineRange>,
+
+    /// Only format lines which have changed in the working tree since the given git revision.
+    /// If no files are provided, all files changed since the revision are formatted.
+    /// Untracked files which are not ignored are formatted in full.
+    #[structopt(long, conflicts_with_all = &["range-start", "range-end", "line-range"])]
+    pub changed_since: Option<String>,
+
+    /// Only format lines which are staged in the git index, compared against `HEAD`. Useful as a pre-commit hook.
+    /// If no files are provided, all files with staged changes are formatted.
+    #[structopt(long, conflicts_with_all = &["range-start", "range-end", "line-range", "changed-since"])]
+    pub staged: bool,
+
+    /// Runs StyLua as a language server, communicating over stdio.
+    /// Supports document, range and on-type formatting requests.
+    #[structopt(long)]
+    pub lsp: bool,
+
     /// Formatting options to apply when formatting code.
     #[structopt(flatten)]
     pub format_opts: FormatOpts,
@@ -100,6 +129,53 @@ pub struct FormatOpts {
     /// The style of quotes to use in string literals.
     #[structopt(long, possible_values = &ArgQuoteStyle::variants(), case_insensitive = true, )]
     pub quote_style: Option<ArgQuoteStyle>,
+    /// The Lua syntax to parse code as.
+    #[structopt(long, possible_values = &ArgLuaVersion::variants(), case_insensitive = true, )]
+    pub syntax: Option<ArgLuaVersion>,
+}
+
+/// A range of lines provided through `--line-range`
+#[derive(Debug, Clone, Copy)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl FromStr for LineRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_line = |line: &str, default: usize| -> Result<usize, String> {
+            if line.is_empty() {
+                return Ok(default);
+            }
+
+            match line.parse::<usize>() {
+                Ok(0) | Err(_) => Err(format!(
+                    "invalid line number `{}`, expected a number starting from 1",
+                    line
+                )),
+                Ok(line) => Ok(line),
+            }
+        };
+
+        let (start, end) = match s.split_once(':') {
+            Some((start, end)) => (parse_line(start, 1)?, parse_line(end, usize::MAX)?),
+            None => {
+                let line = parse_line(s, 1)?;
+                (line, line)
+            }
+        };
+
+        if start > end {
+            return Err(format!(
+                "line range start {} is after the end {}",
+                start, end
+            ));
+        }
+
+        Ok(Self { start, end })
+    }
 }
 
 // Convert [`stylua_lib::Config`] enums into clap-friendly enums
@@ -137,6 +213,7 @@ macro_rules! convert_enum {
 }
 
 convert_enum!(LineEndings, ArgLineEndings, {
+    Auto,
     Unix,
     Windows,
 });
@@ -152,3 +229,10 @@ convert_enum!(QuoteStyle, ArgQuoteStyle, {
     ForceDouble,
     ForceSingle,
 });
+
+convert_enum!(LuaVersion, ArgLuaVersion, {
+    All,
+    Lua51,
+    Lua52,
+    Luau,
+});
@@ -1,25 +1,36 @@
-use crate::{shape::Shape, Config, IndentType, LineEndings, Range as FormatRange};
+use crate::{
+    plugin::FormatterPlugin, shape::Shape, Config, IndentType, LineEndings, Range as FormatRange,
+};
 use full_moon::{
     node::Node,
     tokenizer::{Token, TokenType},
 };
 
-#[derive(Debug, Clone, Copy)]
-pub struct Context {
+/// The context used when formatting, passed down to every formatter
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
     /// The configuration passed to the formatter
     config: Config,
-    /// An optional range of values to format within the file.
-    range: Option<FormatRange>,
+    /// The ranges of values to format within the file. These are sorted by their start bound, and do not overlap.
+    ranges: &'a [FormatRange],
+    /// The plugins to run around formatting nodes, in the order they should be run.
+    plugins: &'a [Box<dyn FormatterPlugin>],
     /// Whether the formatting has currently been disabled. This should occur when we see the relevant comment.
     formatting_disabled: bool,
 }
 
-impl Context {
-    /// Creates a new Context, with the given configuration
-    pub fn new(config: Config, range: Option<FormatRange>) -> Self {
+impl<'a> Context<'a> {
+    /// Creates a new Context, with the given configuration, the sorted, non-overlapping ranges to format
+    /// and the plugins to run
+    pub fn new(
+        config: Config,
+        ranges: &'a [FormatRange],
+        plugins: &'a [Box<dyn FormatterPlugin>],
+    ) -> Self {
         Self {
             config,
-            range,
+            ranges,
+            plugins,
             formatting_disabled: false,
         }
     }
@@ -29,6 +40,11 @@ impl Context {
         self.config
     }
 
+    /// Get the plugins to run when formatting
+    pub fn plugins(&self) -> &'a [Box<dyn FormatterPlugin>] {
+        self.plugins
+    }
+
     /// Determines whether we need to toggle whether formatting is enabled or disabled.
     /// Formatting is toggled on/off whenever we see a `-- stylua: ignore start` or `-- stylua: ignore end` comment respectively.
     // To preserve immutability of Context, we return a new Context with the `formatting_disabled` field toggled or left the same
@@ -66,7 +82,7 @@ impl Context {
     /// Checks whether we should format the given node.
     /// Firstly determine if formatting is disabled (due to the relevant comment)
     /// If not, determine whether the node has an ignore comment present.
-    /// If not, checks whether the provided node is outside the formatting range.
+    /// If not, checks whether the provided node lies outside of every formatting range.
     /// If not, the node should be formatted.
     pub fn should_format_node(&self, node: &impl Node) -> bool {
         // If formatting is disabled we should immediately bailed out.
@@ -74,49 +90,69 @@ impl Context {
             return false;
         }
 
-        // Check comments
-        let leading_trivia = node.surrounding_trivia().0;
-        for trivia in leading_trivia {
-            let comment_lines = match trivia.token_type() {
-                TokenType::SingleLineComment { comment } => comment,
-                TokenType::MultiLineComment { comment, .. } => comment,
-                _ => continue,
-            }
-            .lines()
-            .map(|line| line.trim());
+        if has_ignore_comment(node) {
+            return false;
+        }
 
-            for line in comment_lines {
-                if line == "stylua: ignore" {
-                    return false;
-                }
-            }
+        let (node_start, node_end) = match (node.start_position(), node.end_position()) {
+            (Some(start), Some(end)) => (start.bytes(), end.bytes()),
+            // We cannot tell where the node is, so only check that some range has been selected
+            _ => return !self.ranges.is_empty(),
+        };
+
+        // The ranges are sorted and do not overlap, so the node can only lie within the last range starting before it
+        let index = self
+            .ranges
+            .partition_point(|range| range.start.unwrap_or(0) <= node_start);
+
+        match index.checked_sub(1).map(|index| self.ranges[index]) {
+            Some(range) => range.end.map_or(true, |end_bound| node_end <= end_bound),
+            None => false,
         }
+    }
 
-        if let Some(range) = self.range {
-            let mut in_range = true;
+    /// Checks whether we should descend into the given node to format any blocks within it, when the node itself
+    /// should not be formatted. This is the case when the node only partially overlaps a formatting range,
+    /// and formatting has not been disabled for it.
+    pub fn should_descend_into_node(&self, node: &impl Node) -> bool {
+        if self.formatting_disabled || has_ignore_comment(node) {
+            return false;
+        }
 
-            if let Some(start_bound) = range.start {
-                if let Some(node_start) = node.start_position() {
-                    if node_start.bytes() < start_bound {
-                        in_range = false;
-                    }
-                }
-            }
+        match (node.start_position(), node.end_position()) {
+            (Some(start), Some(end)) => self.ranges.iter().any(|range| {
+                range
+                    .start
+                    .map_or(true, |start_bound| end.bytes() > start_bound)
+                    && range
+                        .end
+                        .map_or(true, |end_bound| start.bytes() < end_bound)
+            }),
+            _ => false,
+        }
+    }
+}
 
-            if let Some(end_bound) = range.end {
-                if let Some(node_end) = node.end_position() {
-                    if node_end.bytes() > end_bound {
-                        in_range = false;
-                    }
-                }
-            }
+/// Determines whether the node has a `-- stylua: ignore` comment preceding it
+fn has_ignore_comment(node: &impl Node) -> bool {
+    let leading_trivia = node.surrounding_trivia().0;
+    for trivia in leading_trivia {
+        let comment_lines = match trivia.token_type() {
+            TokenType::SingleLineComment { comment } => comment,
+            TokenType::MultiLineComment { comment, .. } => comment,
+            _ => continue,
+        }
+        .lines()
+        .map(|line| line.trim());
 
-            in_range
-        } else {
-            // No range provided, therefore always in formatting range
-            true
+        for line in comment_lines {
+            if line == "stylua: ignore" {
+                return true;
+            }
         }
     }
+
+    false
 }
 
 #[macro_export]
@@ -131,11 +167,19 @@ macro_rules! check_should_format {
 /// Returns the relevant line ending string from the [`LineEndings`] enum
 fn line_ending_character(line_endings: LineEndings) -> String {
     match line_endings {
-        LineEndings::Unix => String::from("\n"),
+        // Automatic line endings are resolved before formatting, but fall back to Unix line endings if not
+        LineEndings::Auto | LineEndings::Unix => String::from("\n"),
         LineEndings::Windows => String::from("\r\n"),
     }
 }
 
+/// Converts every line ending within the text to the configured line endings.
+/// Used for the contents of multiline comments and long strings
+pub fn convert_line_endings(ctx: &Context, text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\n', &line_ending_character(ctx.config().line_endings))
+}
+
 /// Creates a new Token containing whitespace for indents, used for trivia
 pub fn create_indent_trivia(ctx: &Context, shape: Shape) -> Token {
     let indent_level = shape.indent().block_indent() + shape.indent().additional_indent();
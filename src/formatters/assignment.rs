@@ -15,7 +15,10 @@ use crate::{
     context::{create_indent_trivia, create_newline_trivia, Context},
     fmt_symbol,
     formatters::{
-        expression::{format_expression, format_var, hang_expression},
+        expression::{
+            finish_punctuated_layout, format_expression_layout, format_var, hang_expression_layout,
+            start_punctuated_layout,
+        },
         general::{
             format_punctuated, format_punctuated_multiline, format_token_reference,
             try_format_punctuated,
@@ -31,7 +34,7 @@ use crate::{
 
 /// Hangs each [`Expression`] in a [`Punctuated`] list.
 /// The Punctuated list is hung multiline at the comma aswell, and each subsequent item after the first is
-/// indented by one.
+/// indented by one. The hooks of any plugins are not run for the expressions, see [`start_punctuated_layout`].
 pub fn hang_punctuated_list(
     ctx: &Context,
     punctuated: &Punctuated<Expression>,
@@ -48,7 +51,7 @@ pub fn hang_punctuated_list(
             shape.reset().increment_additional_indent()
         };
 
-        let mut value = hang_expression(ctx, pair.value(), shape, Some(1));
+        let mut value = hang_expression_layout(ctx, pair.value(), shape, Some(1));
         if idx != 0 {
             value =
                 value.update_leading_trivia(FormatTriviaType::Append(vec![create_indent_trivia(
@@ -96,7 +99,7 @@ fn hang_equal_token(
 }
 
 /// Attempts different formatting tactics on an expression list being assigned (`= foo, bar`), to find the best
-/// formatting output.
+/// formatting output. The hooks of any plugins should already have been run on the expressions.
 fn attempt_assignment_tactics(
     ctx: &Context,
     expressions: &Punctuated<Expression>,
@@ -113,7 +116,7 @@ fn attempt_assignment_tactics(
             ctx,
             expressions,
             hanging_shape.with_infinite_width(),
-            format_expression,
+            format_expression_layout,
         );
 
         if expressions.pairs().any(|pair| {
@@ -130,7 +133,7 @@ fn attempt_assignment_tactics(
                 ctx,
                 expressions,
                 hanging_shape,
-                format_expression,
+                format_expression_layout,
                 None,
             );
 
@@ -147,8 +150,9 @@ fn attempt_assignment_tactics(
                     || shape.take_first_line(&formatted).over_budget()
                 {
                     // Hang the pair, using the original expression for formatting
-                    output_expr
-                        .push(formatted.map(|_| hang_expression(ctx, original, shape, Some(1))))
+                    output_expr.push(
+                        formatted.map(|_| hang_expression_layout(ctx, original, shape, Some(1))),
+                    )
                 } else {
                     // Add the pair as it is
                     output_expr.push(formatted);
@@ -173,7 +177,7 @@ fn attempt_assignment_tactics(
             let shape = shape.reset().increment_additional_indent();
 
             // As we know that there is only a single element in the list, we can extract it to work with it
-            let expression = format_expression(ctx, expression, shape);
+            let expression = format_expression_layout(ctx, expression, shape);
 
             // We need to take all the leading trivia from the expr_list
             let (expression, leading_comments) =
@@ -206,7 +210,7 @@ fn attempt_assignment_tactics(
         let hanging_shape = shape.take_first_line(&strip_trivia(&hanging_expr_list));
 
         // Create an example formatting the expression normally
-        let expr_list = format_punctuated(ctx, expressions, shape, format_expression);
+        let expr_list = format_punctuated(ctx, expressions, shape, format_expression_layout);
         let formatting_shape = shape.take_first_line(&strip_trailing_trivia(&expr_list));
 
         // Find the better format out of the hanging shape or the normal formatting
@@ -220,7 +224,8 @@ fn attempt_assignment_tactics(
                 let equal_token = hang_equal_token(ctx, equal_token, shape, true);
                 // Add the expression list into the indent range, as it will be indented by one
                 let shape = shape.increment_additional_indent();
-                let expr_list = format_punctuated(ctx, expressions, shape, format_expression);
+                let expr_list =
+                    format_punctuated(ctx, expressions, shape, format_expression_layout);
                 (expr_list, equal_token)
             } else {
                 (expr_list, equal_token)
@@ -235,10 +240,13 @@ pub fn format_assignment(ctx: &Context, assignment: &Assignment, shape: Shape) -
     let leading_trivia = vec![create_indent_trivia(ctx, shape)];
     let trailing_trivia = vec![create_newline_trivia(ctx)];
 
+    // The expressions are formatted using several tactics, so only run the plugin hooks for them once
+    let expressions = start_punctuated_layout(ctx, assignment.expressions(), shape);
+
     // Check if the assignment expressions or equal token contain comments. If they do, we bail out of determining any tactics
     // and format multiline
     let contains_comments = trivia_util::token_contains_comments(assignment.equal_token())
-        || assignment.expressions().pairs().any(|pair| {
+        || expressions.pairs().any(|pair| {
             pair.punctuation()
                 .map_or(false, |x| trivia_util::token_contains_comments(x))
                 || trivia_util::expression_contains_inline_comments(pair.value())
@@ -255,9 +263,9 @@ pub fn format_assignment(ctx: &Context, assignment: &Assignment, shape: Shape) -
     let mut equal_token = fmt_symbol!(ctx, assignment.equal_token(), " = ", shape);
     let mut expr_list = format_punctuated(
         ctx,
-        assignment.expressions(),
+        &expressions,
         shape.with_infinite_width(),
-        format_expression,
+        format_expression_layout,
     );
 
     // Test the assignment to see if its over width
@@ -271,10 +279,11 @@ pub fn format_assignment(ctx: &Context, assignment: &Assignment, shape: Shape) -
         let shape = shape + (strip_leading_trivia(&var_list).to_string().len() + 3);
 
         let (new_expr_list, new_equal_token) =
-            attempt_assignment_tactics(ctx, assignment.expressions(), shape, equal_token);
+            attempt_assignment_tactics(ctx, &expressions, shape, equal_token);
         expr_list = new_expr_list;
         equal_token = new_equal_token;
     }
+    let expr_list = finish_punctuated_layout(ctx, expr_list, shape);
 
     // Add necessary trivia
     let var_list = var_list.update_leading_trivia(FormatTriviaType::Append(leading_trivia));
@@ -347,12 +356,15 @@ pub fn format_local_assignment(
     if assignment.expressions().is_empty() {
         format_local_no_assignment(ctx, assignment, shape, leading_trivia, trailing_trivia)
     } else {
+        // The expressions are formatted using several tactics, so only run the plugin hooks for them once
+        let expressions = start_punctuated_layout(ctx, assignment.expressions(), shape);
+
         // Check if the assignment expression or equals token contain comments. If they do, we bail out of determining any tactics
         // and format multiline
         let contains_comments = assignment
             .equal_token()
             .map_or(false, |x| trivia_util::token_contains_comments(x))
-            || assignment.expressions().pairs().any(|pair| {
+            || expressions.pairs().any(|pair| {
                 pair.punctuation()
                     .map_or(false, |x| trivia_util::token_contains_comments(x))
                     || trivia_util::expression_contains_inline_comments(pair.value())
@@ -372,9 +384,9 @@ pub fn format_local_assignment(
         let mut equal_token = fmt_symbol!(ctx, assignment.equal_token().unwrap(), " = ", shape);
         let mut expr_list = format_punctuated(
             ctx,
-            assignment.expressions(),
+            &expressions,
             shape.with_infinite_width(),
-            format_expression,
+            format_expression_layout,
         );
 
         #[cfg(feature = "luau")]
@@ -415,10 +427,11 @@ pub fn format_local_assignment(
                 + (strip_leading_trivia(&name_list).to_string().len() + 6 + 3 + type_specifier_len);
 
             let (new_expr_list, new_equal_token) =
-                attempt_assignment_tactics(ctx, assignment.expressions(), shape, equal_token);
+                attempt_assignment_tactics(ctx, &expressions, shape, equal_token);
             expr_list = new_expr_list;
             equal_token = new_equal_token;
         }
+        let expr_list = finish_punctuated_layout(ctx, expr_list, shape);
 
         // Add necessary trivia
         let expr_list = expr_list.update_trailing_trivia(FormatTriviaType::Append(trailing_trivia));
@@ -4,7 +4,10 @@ use crate::{
     fmt_symbol,
     formatters::{
         assignment::hang_punctuated_list,
-        expression::{format_expression, hang_expression},
+        expression::{
+            finish_punctuated_layout, format_expression_layout, hang_expression_layout,
+            start_punctuated_layout,
+        },
         general::{format_punctuated, format_punctuated_multiline, format_symbol},
         stmt::format_stmt,
         trivia::{
@@ -47,7 +50,9 @@ pub fn format_return(ctx: &Context, return_node: &Return, shape: Shape) -> Retur
 
         let shape = shape + (strip_trivia(return_node.token()).to_string().len() + 1); // 1 = " "
 
-        let returns = return_node.returns();
+        // The returns are formatted using several tactics, so only run the plugin hooks for them once
+        let returns = start_punctuated_layout(ctx, return_node.returns(), shape);
+        let returns = &*returns;
 
         let contains_comments = trivia_util::contains_comments(
             returns.update_trailing_trivia(FormatTriviaType::Replace(Vec::new())), // We can ignore trailing trivia, as that won't affect anything
@@ -62,8 +67,12 @@ pub fn format_return(ctx: &Context, return_node: &Return, shape: Shape) -> Retur
             (true, Punctuated::new())
         } else {
             // Firstly attempt to format the returns onto a single line, using an infinite column width shape
-            let singleline_returns =
-                format_punctuated(ctx, returns, shape.with_infinite_width(), format_expression);
+            let singleline_returns = format_punctuated(
+                ctx,
+                returns,
+                shape.with_infinite_width(),
+                format_expression_layout,
+            );
 
             // Test the return to see if its over width
             let singleline_shape =
@@ -76,8 +85,13 @@ pub fn format_return(ctx: &Context, return_node: &Return, shape: Shape) -> Retur
             if returns.len() > 1 {
                 // Format the punctuated onto multiple lines
                 let hang_level = Some(1);
-                let multiline_returns =
-                    format_punctuated_multiline(ctx, returns, shape, format_expression, hang_level);
+                let multiline_returns = format_punctuated_multiline(
+                    ctx,
+                    returns,
+                    shape,
+                    format_expression_layout,
+                    hang_level,
+                );
 
                 let mut output_returns = Punctuated::new();
 
@@ -98,8 +112,10 @@ pub fn format_return(ctx: &Context, return_node: &Return, shape: Shape) -> Retur
                         || shape.take_first_line(&formatted).over_budget()
                     {
                         // Hang the pair, using the original expression for formatting
-                        output_returns
-                            .push(formatted.map(|_| hang_expression(ctx, original, shape, Some(1))))
+                        output_returns.push(
+                            formatted
+                                .map(|_| hang_expression_layout(ctx, original, shape, Some(1))),
+                        )
                     } else {
                         // Add the pair as it is
                         output_returns.push(formatted);
@@ -113,7 +129,8 @@ pub fn format_return(ctx: &Context, return_node: &Return, shape: Shape) -> Retur
                 let hanging_shape = shape.take_first_line(&strip_trivia(&hanging_returns));
 
                 // Create an example formatting the expression normally
-                let formatted_returns = format_punctuated(ctx, returns, shape, format_expression);
+                let formatted_returns =
+                    format_punctuated(ctx, returns, shape, format_expression_layout);
                 let formatting_shape =
                     shape.take_first_line(&strip_trailing_trivia(&formatted_returns));
 
@@ -130,8 +147,8 @@ pub fn format_return(ctx: &Context, return_node: &Return, shape: Shape) -> Retur
         };
 
         // Add a newline at the end of the list
-        let formatted_returns =
-            formatted_returns.update_trailing_trivia(FormatTriviaType::Append(trailing_trivia));
+        let formatted_returns = finish_punctuated_layout(ctx, formatted_returns, shape)
+            .update_trailing_trivia(FormatTriviaType::Append(trailing_trivia));
 
         Return::new()
             .with_token(token)
@@ -371,6 +388,7 @@ pub fn format_block(ctx: &Context, block: &Block, shape: Shape) -> Block {
 
     while let Some((stmt, semi)) = stmt_iterator.next() {
         ctx = ctx.check_toggle_formatting(stmt);
+        let should_format = ctx.should_format_node(stmt);
 
         let shape = shape.reset();
         let mut stmt = format_stmt(&ctx, stmt, shape);
@@ -383,6 +401,12 @@ pub fn format_block(ctx: &Context, block: &Block, shape: Shape) -> Block {
             found_first_stmt = true;
         }
 
+        // If the stmt is not being formatted, its semicolon should be kept as it was
+        if !should_format {
+            formatted_statements.push((stmt, semi.to_owned()));
+            continue;
+        }
+
         // Need to check next statement if it is a function call, with a parameters expression as the prefix
         // If so, removing a semicolon may lead to ambiguous syntax
         // Ambiguous syntax can only occur if the current statement is a (Local)Assignment, FunctionCall or a Repeat block
@@ -459,6 +483,8 @@ pub fn format_block(ctx: &Context, block: &Block, shape: Shape) -> Block {
         Some((last_stmt, semi)) => {
             ctx = ctx.check_toggle_formatting(last_stmt);
 
+            let should_format = ctx.should_format_node(last_stmt);
+
             let shape = shape.reset();
             let mut last_stmt = format_last_stmt(&ctx, last_stmt, shape);
             // If this is the first stmt, then remove any leading newlines
@@ -468,6 +494,7 @@ pub fn format_block(ctx: &Context, block: &Block, shape: Shape) -> Block {
             // LastStmt will never need a semicolon
             // We need to check if we previously had a semicolon, and keep the comments if so
             let semicolon = match semi {
+                Some(semi) if !should_format => Some(semi.to_owned()),
                 Some(semi) => {
                     let (updated_last_stmt, trivia) =
                         trivia_util::get_last_stmt_trailing_trivia(last_stmt);
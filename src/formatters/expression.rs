@@ -1,11 +1,12 @@
 use full_moon::{
     ast::{
-        span::ContainedSpan, BinOp, Call, Expression, Index, Prefix, Suffix, UnOp, Value, Var,
-        VarExpression,
+        punctuated::Punctuated, span::ContainedSpan, BinOp, Call, Expression, Index, Prefix,
+        Suffix, UnOp, Value, Var, VarExpression,
     },
     node::Node,
     tokenizer::{Symbol, Token, TokenReference, TokenType},
 };
+use std::borrow::Cow;
 use std::boxed::Box;
 
 #[cfg(feature = "luau")]
@@ -28,6 +29,7 @@ use crate::{
             trivia_is_newline,
         },
     },
+    plugin::{run_after_hooks, run_before_hooks},
     shape::Shape,
 };
 
@@ -113,6 +115,76 @@ fn check_excess_parentheses(internal_expression: &Expression) -> bool {
 
 /// Formats an Expression node
 pub fn format_expression(ctx: &Context, expression: &Expression, shape: Shape) -> Expression {
+    let expression = start_expression_layout(ctx, expression, shape);
+    let formatted = format_expression_layout(ctx, &expression, shape);
+    finish_expression_layout(ctx, formatted, shape)
+}
+
+/// Runs the `before` hooks of any plugins on an expression which will be laid out in several ways (such as formatting
+/// it normally and hanging it) to find the best output. Each layout should be created from the returned expression
+/// using [`format_expression_layout`] or [`hang_expression_layout`], which do not run the hooks again, and the `after`
+/// hooks then run on the layout which is kept using [`finish_expression_layout`].
+pub fn start_expression_layout<'a>(
+    ctx: &Context,
+    expression: &'a Expression,
+    shape: Shape,
+) -> Cow<'a, Expression> {
+    run_before_hooks(ctx, expression, shape, |plugin, ctx, expression, shape| {
+        plugin.before_format_expression(ctx, expression, shape)
+    })
+}
+
+/// Runs the `after` hooks of any plugins on the layout of an expression which is kept
+pub fn finish_expression_layout(ctx: &Context, expression: Expression, shape: Shape) -> Expression {
+    run_after_hooks(ctx, expression, shape, |plugin, ctx, expression, shape| {
+        plugin.after_format_expression(ctx, expression, shape)
+    })
+}
+
+/// Runs the `before` hooks of any plugins on each expression in a list, in the same way as [`start_expression_layout`]
+pub fn start_punctuated_layout<'a>(
+    ctx: &Context,
+    punctuated: &'a Punctuated<Expression>,
+    shape: Shape,
+) -> Cow<'a, Punctuated<Expression>> {
+    if ctx.plugins().is_empty() {
+        return Cow::Borrowed(punctuated);
+    }
+
+    Cow::Owned(
+        punctuated
+            .pairs()
+            .map(|pair| {
+                pair.to_owned()
+                    .map(|expression| start_expression_layout(ctx, &expression, shape).into_owned())
+            })
+            .collect(),
+    )
+}
+
+/// Runs the `after` hooks of any plugins on each expression in the layout of a list which is kept
+pub fn finish_punctuated_layout(
+    ctx: &Context,
+    punctuated: Punctuated<Expression>,
+    shape: Shape,
+) -> Punctuated<Expression> {
+    if ctx.plugins().is_empty() {
+        return punctuated;
+    }
+
+    punctuated
+        .into_pairs()
+        .map(|pair| pair.map(|expression| finish_expression_layout(ctx, expression, shape)))
+        .collect()
+}
+
+/// Formats an Expression node, without running the hooks of any plugins for it.
+/// The hooks are still run for the nodes within the expression.
+pub fn format_expression_layout(
+    ctx: &Context,
+    expression: &Expression,
+    shape: Shape,
+) -> Expression {
     format_expression_internal(ctx, expression, ExpressionContext::Standard, shape)
 }
 
@@ -581,12 +653,25 @@ enum ExpressionSide {
     Right,
 }
 
+/// Hangs an expression which is part of a binary operator chain, running the hooks of any plugins around it
 fn hang_binop_expression(
     ctx: &Context,
     expression: Expression,
     top_binop: BinOp,
     shape: Shape,
     lhs_range: Option<LeftmostRangeHang>,
+) -> Expression {
+    let expression = start_expression_layout(ctx, &expression, shape).into_owned();
+    let hanging = hang_binop_expression_layout(ctx, expression, top_binop, shape, lhs_range);
+    finish_expression_layout(ctx, hanging, shape)
+}
+
+fn hang_binop_expression_layout(
+    ctx: &Context,
+    expression: Expression,
+    top_binop: BinOp,
+    shape: Shape,
+    lhs_range: Option<LeftmostRangeHang>,
 ) -> Expression {
     let full_expression = expression.to_owned();
 
@@ -692,6 +777,20 @@ fn hang_binop_expression(
     }
 }
 
+/// Hangs an expression contained within the expression being hung, running the hooks of any plugins around it
+fn format_hanging_expression(
+    ctx: &Context,
+    expression: &Expression,
+    shape: Shape,
+    expression_context: ExpressionContext,
+    lhs_range: Option<LeftmostRangeHang>,
+) -> Expression {
+    let expression = start_expression_layout(ctx, expression, shape);
+    let hanging =
+        format_hanging_expression_(ctx, &expression, shape, expression_context, lhs_range);
+    finish_expression_layout(ctx, hanging, shape)
+}
+
 /// Internal expression formatter, where the binop is also hung
 fn format_hanging_expression_(
     ctx: &Context,
@@ -710,7 +809,7 @@ fn format_hanging_expression_(
         } => {
             let value = Box::new(match &**value {
                 Value::ParenthesesExpression(expression) => {
-                    Value::ParenthesesExpression(format_hanging_expression_(
+                    Value::ParenthesesExpression(format_hanging_expression(
                         ctx,
                         expression,
                         shape,
@@ -751,26 +850,27 @@ fn format_hanging_expression_(
 
             // If the context is for a prefix, we should always keep the parentheses, as they are always required
             if use_internal_expression && !matches!(expression_context, ExpressionContext::Prefix) {
-                format_hanging_expression_(
-                    ctx,
-                    expression,
-                    lhs_shape,
-                    expression_context,
-                    lhs_range,
-                )
+                format_hanging_expression(ctx, expression, lhs_shape, expression_context, lhs_range)
             } else {
                 let contained = format_contained_span(ctx, &contained, lhs_shape);
 
                 // Provide a sample formatting to see how large it is
                 // Examine the expression itself to see if needs to be split onto multiple lines
-                let formatted_expression = format_expression(ctx, expression, lhs_shape + 1); // 1 = opening parentheses
+                let singleline_shape = lhs_shape + 1; // 1 = opening parentheses
+                let expression = start_expression_layout(ctx, expression, singleline_shape);
+                let formatted_expression =
+                    format_expression_layout(ctx, &expression, singleline_shape);
 
                 let expression_str = formatted_expression.to_string();
                 if !lhs_shape.add_width(2 + expression_str.len()).over_budget() {
                     // The expression inside the parentheses is small, we do not need to break it down further
                     return Expression::Parentheses {
                         contained,
-                        expression: Box::new(formatted_expression),
+                        expression: Box::new(finish_expression_layout(
+                            ctx,
+                            formatted_expression,
+                            singleline_shape,
+                        )),
                     };
                 }
 
@@ -795,14 +895,20 @@ fn format_hanging_expression_(
                     ])),
                 );
 
+                let hanging_expression = format_hanging_expression_(
+                    ctx,
+                    &expression,
+                    expression_shape,
+                    ExpressionContext::Standard,
+                    None,
+                );
+
                 Expression::Parentheses {
                     contained,
-                    expression: Box::new(format_hanging_expression_(
+                    expression: Box::new(finish_expression_layout(
                         ctx,
-                        &expression,
+                        hanging_expression,
                         expression_shape,
-                        ExpressionContext::Standard,
-                        None,
                     )),
                 }
             }
@@ -811,7 +917,7 @@ fn format_hanging_expression_(
             let unop = format_unop(ctx, unop, shape);
             let shape = shape + strip_leading_trivia(&unop).to_string().len();
             let expression =
-                format_hanging_expression_(ctx, &expression, shape, expression_context, lhs_range);
+                format_hanging_expression(ctx, &expression, shape, expression_context, lhs_range);
 
             Expression::UnaryOperator {
                 unop,
@@ -827,9 +933,12 @@ fn format_hanging_expression_(
             let singleline_shape =
                 shape.take_last_line(&lhs) + strip_trivia(binop).to_string().len() + 1;
 
-            let mut new_rhs = hang_binop_expression(
+            // The rhs is laid out both on the same line and hanging, so the plugin hooks are only run once for it
+            let rhs = start_expression_layout(ctx, rhs, singleline_shape);
+            let mut rhs_shape = singleline_shape;
+            let mut new_rhs = hang_binop_expression_layout(
                 ctx,
-                *rhs.to_owned(),
+                rhs.clone().into_owned(),
                 binop.to_owned(),
                 singleline_shape,
                 None,
@@ -843,13 +952,13 @@ fn format_hanging_expression_(
                     .any(trivia_util::trivia_is_comment)
                 || (shape.take_last_line(&lhs) + format!("{}{}", binop, rhs).len()).over_budget()
             {
-                let hanging_shape = shape.reset() + strip_trivia(binop).to_string().len() + 1;
-                new_binop = hang_binop(ctx, binop.to_owned(), shape, rhs);
-                new_rhs = hang_binop_expression(
+                rhs_shape = shape.reset() + strip_trivia(binop).to_string().len() + 1;
+                new_binop = hang_binop(ctx, binop.to_owned(), shape, &rhs);
+                new_rhs = hang_binop_expression_layout(
                     ctx,
-                    *rhs.to_owned(),
+                    rhs.into_owned(),
                     binop.to_owned(),
-                    hanging_shape,
+                    rhs_shape,
                     None,
                 )
                 .update_leading_trivia(FormatTriviaType::Replace(Vec::new()));
@@ -858,18 +967,32 @@ fn format_hanging_expression_(
             Expression::BinaryOperator {
                 lhs: Box::new(lhs),
                 binop: new_binop,
-                rhs: Box::new(new_rhs),
+                rhs: Box::new(finish_expression_layout(ctx, new_rhs, rhs_shape)),
             }
         }
         other => panic!("unknown node {:?}", other),
     }
 }
 
+/// Hangs an Expression node at its binary operators, where possible
 pub fn hang_expression(
     ctx: &Context,
     expression: &Expression,
     shape: Shape,
     hang_level: Option<usize>,
+) -> Expression {
+    let expression = start_expression_layout(ctx, expression, shape);
+    let hanging = hang_expression_layout(ctx, &expression, shape, hang_level);
+    finish_expression_layout(ctx, hanging, shape)
+}
+
+/// Hangs an Expression node, without running the hooks of any plugins for it.
+/// The hooks are still run for the nodes within the expression.
+pub fn hang_expression_layout(
+    ctx: &Context,
+    expression: &Expression,
+    shape: Shape,
+    hang_level: Option<usize>,
 ) -> Expression {
     let original_additional_indent_level = shape.indent().additional_indent();
     let shape = match hang_level {
@@ -8,13 +8,16 @@ use full_moon::tokenizer::{Symbol, Token, TokenReference, TokenType};
 use std::boxed::Box;
 
 #[cfg(feature = "luau")]
-use crate::formatters::luau::{format_generic_declaration, format_type_specifier};
+use crate::formatters::luau::{format_generic_declaration, format_type_specifier, hang_type_info};
 use crate::{
     context::{create_indent_trivia, create_newline_trivia, Context},
     fmt_symbol,
     formatters::{
         block::format_block,
-        expression::{format_expression, format_prefix, format_suffix, hang_expression},
+        expression::{
+            finish_expression_layout, format_expression, format_expression_layout, format_prefix,
+            format_suffix, hang_expression_layout, start_expression_layout,
+        },
         general::{
             format_contained_span, format_end_token, format_punctuated, format_symbol,
             format_token_reference, EndTokenType,
@@ -376,9 +379,12 @@ pub fn format_function_args(
                 for argument in arguments.pairs() {
                     let shape = shape.reset(); // Argument is on a new line, so reset the shape
 
+                    // The argument may be formatted several times, so only run the plugin hooks for it once
+                    let value = start_expression_layout(ctx, argument.value(), shape);
+
                     // First format the argument assuming infinite width
                     let infinite_width_argument =
-                        format_expression(ctx, argument.value(), shape.with_infinite_width());
+                        format_expression_layout(ctx, &value, shape.with_infinite_width());
 
                     // If the argument fits, great! Otherwise, see if we can hang the expression
                     // If we can, use that instead (as it provides a nicer output). If not, format normally without infinite width
@@ -386,17 +392,20 @@ pub fn format_function_args(
                         .add_width(strip_trivia(&infinite_width_argument).to_string().len())
                         .over_budget()
                     {
-                        if trivia_util::can_hang_expression(argument.value()) {
-                            hang_expression(ctx, argument.value(), shape, Some(1))
+                        if trivia_util::can_hang_expression(&value) {
+                            hang_expression_layout(ctx, &value, shape, Some(1))
                         } else {
-                            format_expression(ctx, argument.value(), shape)
+                            format_expression_layout(ctx, &value, shape)
                         }
                     } else {
                         infinite_width_argument
-                    }
-                    .update_leading_trivia(FormatTriviaType::Append(vec![create_indent_trivia(
-                        ctx, shape,
-                    )]));
+                    };
+
+                    let formatted_argument =
+                        finish_expression_layout(ctx, formatted_argument, shape)
+                            .update_leading_trivia(FormatTriviaType::Append(vec![
+                                create_indent_trivia(ctx, shape),
+                            ]));
 
                     let punctuation = match argument.punctuation() {
                         Some(punctuation) => {
@@ -654,8 +663,23 @@ pub fn format_function_body(
             .map(|x| x.map(|specifier| format_type_specifier(ctx, specifier, shape)))
             .collect();
 
+        // The return type is placed straight after the closing parentheses of the parameters
+        let return_type_shape = match multiline_params {
+            true => shape.reset() + 1,                                     // 1 = ")"
+            false => shape + (formatted_parameters.to_string().len() + 2), // 2 = "()"
+        };
+
         return_type = function_body.return_type().map(|return_type| {
-            let formatted = format_type_specifier(ctx, return_type, shape);
+            let mut formatted = format_type_specifier(ctx, return_type, shape);
+
+            // Hang the return type if it still goes over budget after the parameters
+            if (return_type_shape + formatted.to_string().len()).over_budget() {
+                let hanging_shape = shape.reset().increment_additional_indent();
+                let type_info =
+                    hang_type_info(ctx, formatted.type_info().to_owned(), hanging_shape);
+                formatted = formatted.with_type_info(type_info);
+            }
+
             added_trailing_trivia = true;
             let trivia = if block_empty {
                 vec![Token::new(TokenType::spaces(1))]
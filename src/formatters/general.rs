@@ -1,6 +1,6 @@
 use crate::{
     check_should_format,
-    context::{create_indent_trivia, create_newline_trivia, Context},
+    context::{convert_line_endings, create_indent_trivia, create_newline_trivia, Context},
     formatters::{
         trivia::{FormatTriviaType, UpdateTrailingTrivia},
         trivia_util,
@@ -113,8 +113,14 @@ fn format_token(
         } => {
             // If we have a brackets string, don't mess with it
             if let StringLiteralQuoteType::Brackets = quote_type {
+                let literal = if ctx.config().normalise_multiline_line_endings {
+                    convert_line_endings(ctx, literal).into()
+                } else {
+                    literal.to_owned()
+                };
+
                 TokenType::StringLiteral {
-                    literal: literal.to_owned(),
+                    literal,
                     multi_line: *multi_line,
                     quote_type: StringLiteralQuoteType::Brackets,
                 }
@@ -201,9 +207,15 @@ fn format_token(
                 trailing_trivia = Some(vec![create_newline_trivia(ctx)]);
             }
 
+            let comment = if ctx.config().normalise_multiline_line_endings {
+                convert_line_endings(ctx, comment).into()
+            } else {
+                comment.to_owned()
+            };
+
             TokenType::MultiLineComment {
                 blocks: *blocks,
-                comment: comment.to_owned(),
+                comment,
             }
         }
         TokenType::Whitespace { characters } => TokenType::Whitespace {
@@ -248,6 +248,18 @@ pub fn format_type_info(ctx: &Context, type_info: &TypeInfo, shape: Shape) -> Ty
                 TableType::SingleLine => {
                     format_singleline_table(ctx, braces, fields, format_type_field, shape)
                 }
+                TableType::MultiLine if ctx.config().align_type_fields => {
+                    let key_width = type_fields_key_width(ctx, fields, shape);
+                    format_multiline_table(
+                        ctx,
+                        braces,
+                        fields,
+                        |ctx, type_field, table_type, shape| {
+                            format_aligned_type_field(ctx, type_field, table_type, key_width, shape)
+                        },
+                        shape,
+                    )
+                }
                 TableType::MultiLine => {
                     format_multiline_table(ctx, braces, fields, format_type_field, shape)
                 }
@@ -319,6 +331,10 @@ pub fn hang_type_info(ctx: &Context, type_info: TypeInfo, shape: Shape) -> TypeI
             ])),
             right: Box::new(hang_type_info(ctx, *right, shape)),
         },
+        TypeInfo::Variadic { ellipse, type_info } => TypeInfo::Variadic {
+            ellipse,
+            type_info: Box::new(hang_type_info(ctx, *type_info, shape)),
+        },
         _ => type_info,
     }
 }
@@ -393,6 +409,42 @@ pub fn format_type_field(
     type_field: &TypeField,
     table_type: TableType,
     shape: Shape,
+) -> (TypeField, Vec<Token>) {
+    format_type_field_internal(ctx, type_field, table_type, None, shape)
+}
+
+/// Determines the width of the longest single-line key in the fields of a type table, used to align the field types
+fn type_fields_key_width(ctx: &Context, fields: &Punctuated<TypeField>, shape: Shape) -> usize {
+    fields
+        .iter()
+        .map(|type_field| {
+            format_type_field_key(ctx, type_field.key(), FormatTriviaType::NoChange, shape)
+        })
+        .map(|key| strip_leading_trivia(&key).to_string())
+        .filter(|key| !key.contains('\n'))
+        .map(|key| key.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Formats a [`TypeField`] present inside of a multiline [`TypeInfo::Table`], padding the space after the colon so that
+/// the types of all fields with keys up to `key_width` wide start in the same column
+fn format_aligned_type_field(
+    ctx: &Context,
+    type_field: &TypeField,
+    table_type: TableType,
+    key_width: usize,
+    shape: Shape,
+) -> (TypeField, Vec<Token>) {
+    format_type_field_internal(ctx, type_field, table_type, Some(key_width), shape)
+}
+
+fn format_type_field_internal(
+    ctx: &Context,
+    type_field: &TypeField,
+    table_type: TableType,
+    key_width: Option<usize>,
+    shape: Shape,
 ) -> (TypeField, Vec<Token>) {
     let leading_trivia = match table_type {
         TableType::MultiLine => FormatTriviaType::Append(vec![create_indent_trivia(ctx, shape)]),
@@ -400,8 +452,23 @@ pub fn format_type_field(
     };
 
     let key = format_type_field_key(ctx, type_field.key(), leading_trivia, shape);
-    let colon_token = fmt_symbol!(ctx, type_field.colon_token(), ": ", shape);
-    let shape = shape + (strip_leading_trivia(&key).to_string().len() + 2);
+    let mut colon_token = fmt_symbol!(ctx, type_field.colon_token(), ": ", shape);
+    let key_length = strip_leading_trivia(&key).to_string().len();
+
+    // Pad out the colon so that the type lines up with the other fields, unless the key is too long to be aligned
+    let padding = match key_width {
+        Some(key_width) if key_width > key_length => {
+            let padding = key_width - key_length;
+            colon_token =
+                colon_token.update_trailing_trivia(FormatTriviaType::Replace(vec![Token::new(
+                    TokenType::spaces(padding + 1),
+                )]));
+            padding
+        }
+        _ => 0,
+    };
+
+    let shape = shape + (key_length + 2 + padding);
     let mut value = format_type_info(ctx, type_field.value(), shape);
 
     let trailing_trivia = type_info_trailing_trivia(&value);
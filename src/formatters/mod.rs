@@ -1,4 +1,6 @@
-use crate::{context::Context, shape::Shape, Config};
+use crate::{
+    context::Context, normalise_ranges, plugin::FormatterPlugin, shape::Shape, Config, Range,
+};
 use full_moon::ast::Ast;
 
 pub mod assignment;
@@ -19,24 +21,45 @@ pub mod trivia_util;
 use block::format_block;
 use general::format_eof;
 
-pub struct CodeFormatter {
-    /// The formatting context
-    context: Context,
+/// Formats a full-moon AST, using the provided configuration and plugins.
+/// Unlike `format_code` and `format_ast`, inline configuration within the code is not applied.
+pub struct CodeFormatter<'a> {
+    /// The configuration to format with
+    config: Config,
+    /// The sorted, non-overlapping ranges to format
+    ranges: Vec<Range>,
+    /// The plugins to run around the formatting of nodes
+    plugins: &'a [Box<dyn FormatterPlugin>],
 }
 
-impl CodeFormatter {
-    /// Creates a new CodeFormatter, with the given configuration
-    pub fn new(config: Config, range: Option<crate::Range>) -> Self {
+impl<'a> CodeFormatter<'a> {
+    /// Creates a new CodeFormatter, with the given configuration, the ranges to format and any plugins to run around
+    /// the formatting of nodes. The ranges do not need to be sorted, and may overlap. If `None` is provided,
+    /// the whole AST is formatted, whilst an empty slice of ranges leaves the AST unchanged.
+    pub fn new(
+        config: Config,
+        ranges: Option<&[Range]>,
+        plugins: &'a [Box<dyn FormatterPlugin>],
+    ) -> Self {
+        let ranges = match ranges {
+            Some(ranges) => normalise_ranges(ranges),
+            None => vec![Range::from_values(None, None)],
+        };
+
         CodeFormatter {
-            context: Context::new(config, range),
+            config,
+            ranges,
+            plugins,
         }
     }
 
-    /// Runs the formatter over the given AST
+    /// Runs the formatter over the given AST.
+    /// `LineEndings::Auto` is not detected from the AST, and is formatted as Unix line endings.
     pub fn format(&self, ast: Ast) -> Ast {
-        let shape = Shape::new(&self.context);
-        let new_block = format_block(&self.context, ast.nodes(), shape);
-        let new_eof = format_eof(&self.context, ast.eof(), shape);
+        let context = Context::new(self.config, &self.ranges, self.plugins);
+        let shape = Shape::new(&context);
+        let new_block = format_block(&context, ast.nodes(), shape);
+        let new_eof = format_eof(&context, ast.eof(), shape);
 
         ast.with_nodes(new_block).with_eof(new_eof)
     }
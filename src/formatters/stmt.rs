@@ -6,7 +6,6 @@ use crate::formatters::luau::{
     format_type_specifier,
 };
 use crate::{
-    check_should_format,
     context::{create_indent_trivia, create_newline_trivia, Context},
     fmt_symbol,
     formatters::{
@@ -22,6 +21,7 @@ use crate::{
         },
         trivia_util,
     },
+    plugin::format_with_plugins,
     shape::Shape,
 };
 use full_moon::ast::{
@@ -464,9 +464,111 @@ pub fn format_function_call_stmt(
     )
 }
 
+/// Formats the blocks within a statement which only partially overlaps the formatting range, leaving the rest of the
+/// statement untouched. This allows the statements within the block which do lie in the range to still be formatted.
+/// Only the blocks of the statement itself are formatted. Anonymous function bodies within its expressions are left untouched.
+fn format_stmt_blocks(ctx: &Context, stmt: &Stmt, shape: Shape) -> Stmt {
+    let block_shape = shape.reset().increment_block_indent();
+
+    match stmt {
+        Stmt::Do(do_block) => Stmt::Do(do_block.to_owned().with_block(format_block(
+            ctx,
+            do_block.block(),
+            block_shape,
+        ))),
+        Stmt::FunctionDeclaration(function_declaration) => {
+            let body = function_declaration.body();
+            Stmt::FunctionDeclaration(
+                function_declaration.to_owned().with_body(
+                    body.to_owned()
+                        .with_block(format_block(ctx, body.block(), block_shape)),
+                ),
+            )
+        }
+        Stmt::GenericFor(generic_for) => Stmt::GenericFor(
+            generic_for
+                .to_owned()
+                .with_block(format_block(ctx, generic_for.block(), block_shape)),
+        ),
+        Stmt::If(if_node) => {
+            let else_if = if_node.else_if().map(|else_if| {
+                else_if
+                    .iter()
+                    .map(|else_if| {
+                        else_if.to_owned().with_block(format_block(
+                            ctx,
+                            else_if.block(),
+                            block_shape,
+                        ))
+                    })
+                    .collect()
+            });
+            let else_block = if_node
+                .else_block()
+                .map(|else_block| format_block(ctx, else_block, block_shape));
+
+            Stmt::If(
+                if_node
+                    .to_owned()
+                    .with_block(format_block(ctx, if_node.block(), block_shape))
+                    .with_else_if(else_if)
+                    .with_else(else_block),
+            )
+        }
+        Stmt::LocalFunction(local_function) => {
+            let body = local_function.body();
+            Stmt::LocalFunction(
+                local_function
+                    .to_owned()
+                    .with_body(body.to_owned().with_block(format_block(
+                        ctx,
+                        body.block(),
+                        block_shape,
+                    ))),
+            )
+        }
+        Stmt::NumericFor(numeric_for) => Stmt::NumericFor(
+            numeric_for
+                .to_owned()
+                .with_block(format_block(ctx, numeric_for.block(), block_shape)),
+        ),
+        Stmt::Repeat(repeat_block) => {
+            Stmt::Repeat(repeat_block.to_owned().with_block(format_block(
+                ctx,
+                repeat_block.block(),
+                block_shape,
+            )))
+        }
+        Stmt::While(while_block) => Stmt::While(while_block.to_owned().with_block(format_block(
+            ctx,
+            while_block.block(),
+            block_shape,
+        ))),
+        other => other.to_owned(),
+    }
+}
+
 pub fn format_stmt(ctx: &Context, stmt: &Stmt, shape: Shape) -> Stmt {
-    check_should_format!(ctx, stmt);
+    if !ctx.should_format_node(stmt) {
+        // The statement may only partially overlap the range, in which case we can still format the blocks within it
+        if ctx.should_descend_into_node(stmt) {
+            return format_stmt_blocks(ctx, stmt, shape);
+        }
+
+        return stmt.to_owned();
+    }
+
+    format_with_plugins(
+        ctx,
+        stmt,
+        shape,
+        |plugin, ctx, stmt, shape| plugin.before_format_stmt(ctx, stmt, shape),
+        |plugin, ctx, stmt, shape| plugin.after_format_stmt(ctx, stmt, shape),
+        format_stmt_internal,
+    )
+}
 
+fn format_stmt_internal(ctx: &Context, stmt: &Stmt, shape: Shape) -> Stmt {
     fmt_stmt!(ctx, stmt, shape, {
         Assignment = format_assignment,
         Do = format_do_block,
@@ -2,11 +2,15 @@ use crate::{
     context::{create_indent_trivia, create_newline_trivia, Context},
     fmt_symbol,
     formatters::{
-        expression::{format_expression, hang_expression},
+        expression::{
+            finish_expression_layout, format_expression, format_expression_layout,
+            hang_expression_layout, start_expression_layout,
+        },
         general::{format_contained_span, format_end_token, format_token_reference, EndTokenType},
         trivia::{strip_trivia, FormatTriviaType, UpdateLeadingTrivia, UpdateTrailingTrivia},
         trivia_util,
     },
+    plugin::format_with_plugins,
     shape::Shape,
 };
 use full_moon::ast::{
@@ -53,17 +57,20 @@ fn format_field(
             let equal = fmt_symbol!(ctx, equal, " = ", shape);
             let shape = shape.take_last_line(&key) + (2 + 3); // 2 = brackets, 3 = " = "
 
-            let singleline_value = format_expression(ctx, value, shape)
+            // The value may be formatted twice, so only run the plugin hooks for it once
+            let value = start_expression_layout(ctx, value, shape);
+            let singleline_value = format_expression_layout(ctx, &value, shape)
                 .update_trailing_trivia(FormatTriviaType::Replace(vec![])); // We will remove all the trivia from this value, and place it after the comma
 
-            let value = if trivia_util::can_hang_expression(value)
+            let value = if trivia_util::can_hang_expression(&value)
                 && shape.take_first_line(&singleline_value).over_budget()
             {
-                hang_expression(ctx, value, shape, Some(1))
+                hang_expression_layout(ctx, &value, shape, Some(1))
                     .update_trailing_trivia(FormatTriviaType::Replace(vec![]))
             } else {
                 singleline_value
             };
+            let value = finish_expression_layout(ctx, value, shape);
 
             Field::ExpressionKey {
                 brackets,
@@ -78,41 +85,47 @@ fn format_field(
             let equal = fmt_symbol!(ctx, equal, " = ", shape);
             let shape = shape + (strip_trivia(&key).to_string().len() + 3); // 3 = " = "
 
-            let singleline_value = format_expression(ctx, value, shape)
+            // The value may be formatted twice, so only run the plugin hooks for it once
+            let value = start_expression_layout(ctx, value, shape);
+            let singleline_value = format_expression_layout(ctx, &value, shape)
                 .update_trailing_trivia(FormatTriviaType::Replace(vec![])); // We will remove all the trivia from this value, and place it after the comma
 
-            let value = if trivia_util::can_hang_expression(value)
+            let value = if trivia_util::can_hang_expression(&value)
                 && shape.take_first_line(&singleline_value).over_budget()
             {
-                hang_expression(ctx, value, shape, Some(1))
+                hang_expression_layout(ctx, &value, shape, Some(1))
                     .update_trailing_trivia(FormatTriviaType::Replace(vec![]))
             } else {
                 singleline_value
             };
+            let value = finish_expression_layout(ctx, value, shape);
 
             Field::NameKey { key, equal, value }
         }
         Field::NoKey(expression) => {
             trailing_trivia = trivia_util::get_expression_trailing_trivia(expression);
-            let formatted_expression = format_expression(ctx, expression, shape);
+
+            // The expression may be formatted twice, so only run the plugin hooks for it once
+            let expression = start_expression_layout(ctx, expression, shape);
+            let formatted_expression = format_expression_layout(ctx, &expression, shape);
 
             if let TableType::MultiLine = table_type {
                 // If still over budget, hang the expression
-                let formatted_expression = if trivia_util::can_hang_expression(expression)
+                let formatted_expression = if trivia_util::can_hang_expression(&expression)
                     && shape.take_first_line(&formatted_expression).over_budget()
                 {
-                    hang_expression(ctx, expression, shape, Some(1))
+                    hang_expression_layout(ctx, &expression, shape, Some(1))
                 } else {
                     formatted_expression
                 };
 
                 Field::NoKey(
-                    formatted_expression
+                    finish_expression_layout(ctx, formatted_expression, shape)
                         .update_leading_trivia(leading_trivia)
                         .update_trailing_trivia(FormatTriviaType::Replace(vec![])),
                 )
             } else {
-                Field::NoKey(formatted_expression)
+                Field::NoKey(finish_expression_layout(ctx, formatted_expression, shape))
             }
         }
 
@@ -338,6 +351,25 @@ pub fn format_table_constructor(
     ctx: &Context,
     table_constructor: &TableConstructor,
     shape: Shape,
+) -> TableConstructor {
+    format_with_plugins(
+        ctx,
+        table_constructor,
+        shape,
+        |plugin, ctx, table_constructor, shape| {
+            plugin.before_format_table_constructor(ctx, table_constructor, shape)
+        },
+        |plugin, ctx, table_constructor, shape| {
+            plugin.after_format_table_constructor(ctx, table_constructor, shape)
+        },
+        format_table_constructor_internal,
+    )
+}
+
+fn format_table_constructor_internal(
+    ctx: &Context,
+    table_constructor: &TableConstructor,
+    shape: Shape,
 ) -> TableConstructor {
     let (start_brace, end_brace) = table_constructor.braces().tokens();
 
@@ -0,0 +1,100 @@
+use crate::{Config, Error, ErrorLocation};
+use serde::de::DeserializeOwned;
+
+/// Parses the value of an option within an inline configuration comment.
+/// Values are read as TOML values where possible (e.g. `80` or `true`), otherwise as a string (e.g. `ForceSingle`).
+fn parse_value<T: DeserializeOwned>(key: &str, value: &str) -> Result<T, String> {
+    let toml_value = toml::from_str::<toml::value::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_owned()));
+
+    toml_value
+        .try_into()
+        .map_err(|_| format!("invalid value `{}` for option `{}`", value, key))
+}
+
+/// Applies a single `key=value` option from an inline configuration comment to the config
+fn apply_option(config: Config, key: &str, value: &str) -> Result<Config, String> {
+    Ok(match key {
+        "column_width" => config.with_column_width(parse_value(key, value)?),
+        "line_endings" => config.with_line_endings(parse_value(key, value)?),
+        "indent_type" => config.with_indent_type(parse_value(key, value)?),
+        "indent_width" => config.with_indent_width(parse_value(key, value)?),
+        "quote_style" => config.with_quote_style(parse_value(key, value)?),
+        "no_call_parentheses" => config.with_no_call_parentheses(parse_value(key, value)?),
+        "syntax" => config.with_syntax(parse_value(key, value)?),
+        "normalise_multiline_line_endings" => {
+            config.with_normalise_multiline_line_endings(parse_value(key, value)?)
+        }
+        "align_type_fields" => config.with_align_type_fields(parse_value(key, value)?),
+        _ => return Err(format!("unknown option `{}`", key)),
+    })
+}
+
+/// Applies the options of a `-- stylua: key=value, key=value` comment to the config
+fn apply_options(config: Config, options: &str) -> Result<Config, String> {
+    options
+        .split(',')
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .try_fold(config, |config, option| match option.split_once('=') {
+            Some((key, value)) => apply_option(config, key.trim(), value.trim()),
+            None => Err(format!("expected `key=value`, found `{}`", option)),
+        })
+}
+
+/// Applies any inline configuration within the header comments of the code to the config.
+/// Inline configuration is given as `-- stylua: column_width=80, quote_style=ForceSingle`, and overrides the provided config
+/// for this code only. `-- !syntax <version>` is also accepted as shorthand for `-- stylua: syntax=<version>`.
+/// `-- stylua:` comments which do not contain any `key=value` options, such as `-- stylua: ignore`, are skipped.
+pub fn apply_inline_config(code: &str, mut config: Config) -> Result<Config, Error> {
+    let mut offset = 0;
+
+    for (index, line) in code.split_inclusive('\n').enumerate() {
+        let line_start = offset;
+        offset += line.len();
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || (index == 0 && trimmed.starts_with("#!")) {
+            continue;
+        }
+
+        // Stop searching once we reach the code, the header is only made up of comments
+        let comment = match trimmed.strip_prefix("--") {
+            Some(comment) => comment.trim(),
+            None => break,
+        };
+
+        let result = if let Some(syntax) = comment
+            .strip_prefix("!syntax")
+            .filter(|syntax| syntax.starts_with(char::is_whitespace))
+        {
+            // `-- !syntax <version>` is shorthand for `-- stylua: syntax=<version>`
+            apply_option(config, "syntax", syntax.trim())
+        } else if let Some(options) = comment.strip_prefix("stylua:") {
+            // Comments such as `-- stylua: ignore`, or prose, are not configuration
+            if !options.contains('=') {
+                continue;
+            }
+            apply_options(config, options)
+        } else {
+            continue;
+        };
+
+        config = result.map_err(|message| {
+            let indent = line.len() - line.trim_start().len();
+            Error::InlineConfigError {
+                message,
+                location: ErrorLocation {
+                    line: index + 1,
+                    column: line[..indent].chars().count() + 1,
+                    start: line_start + indent,
+                    end: line_start + indent + trimmed.len(),
+                },
+            }
+        })?;
+    }
+
+    Ok(config)
+}
@@ -1,12 +1,21 @@
-use anyhow::{format_err, Result};
+use full_moon::node::Node;
 use serde::Deserialize;
+use std::fmt;
 
 #[macro_use]
 mod context;
 mod formatters;
+mod inline_config;
+mod plugin;
 mod shape;
+mod syntax;
 mod verify_ast;
 
+pub use context::Context;
+pub use formatters::CodeFormatter;
+pub use plugin::FormatterPlugin;
+pub use shape::{Indent, Shape};
+
 /// The type of indents to use when indenting
 #[derive(Debug, Copy, Clone, Deserialize)]
 pub enum IndentType {
@@ -25,7 +34,9 @@ impl Default for IndentType {
 /// The type of line endings to use at the end of a line
 #[derive(Debug, Copy, Clone, Deserialize)]
 pub enum LineEndings {
-    // Auto,
+    /// Detect the line endings from the code being formatted, using whichever of LF or CRLF is most common.
+    /// Unix line endings are used if the code contains no line endings.
+    Auto,
     /// Unix Line Endings (LF) - `\n`
     Unix,
     /// Windows Line Endings (CRLF) - `\r\n`
@@ -57,6 +68,49 @@ impl Default for QuoteStyle {
     }
 }
 
+/// The Lua syntax to parse and format code as.
+/// Code using syntax which belongs to a different version, such as Luau type annotations when using `Lua51`,
+/// fails to parse.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
+pub enum LuaVersion {
+    /// Parse using every syntax supported by this build of StyLua
+    All,
+    /// Lua 5.1
+    Lua51,
+    /// Lua 5.2, which adds `goto` and labels. Requires StyLua to be built with the `lua52` feature
+    Lua52,
+    /// Luau, the Roblox dialect of Lua. Requires StyLua to be built with the `luau` feature
+    Luau,
+}
+
+impl LuaVersion {
+    /// Whether this build of StyLua is able to parse the syntax.
+    /// The parser is selected when StyLua is compiled, so Lua 5.2 and Luau code can only be parsed when the
+    /// corresponding cargo feature is enabled.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            LuaVersion::All | LuaVersion::Lua51 => true,
+            LuaVersion::Lua52 => cfg!(feature = "lua52"),
+            LuaVersion::Luau => cfg!(feature = "luau"),
+        }
+    }
+
+    /// The cargo feature required to parse the syntax, if any
+    fn required_feature(&self) -> Option<&'static str> {
+        match self {
+            LuaVersion::All | LuaVersion::Lua51 => None,
+            LuaVersion::Lua52 => Some("lua52"),
+            LuaVersion::Luau => Some("luau"),
+        }
+    }
+}
+
+impl Default for LuaVersion {
+    fn default() -> Self {
+        LuaVersion::All
+    }
+}
+
 /// An optional formatting range.
 /// If provided, only content within these boundaries (inclusive) will be formatted.
 /// Both boundaries are optional, and are given as byte offsets from the beginning of the file.
@@ -72,6 +126,77 @@ impl Range {
     pub fn from_values(start: Option<usize>, end: Option<usize>) -> Self {
         Self { start, end }
     }
+
+    /// Creates a new formatting range covering the given lines of the code, both inclusive.
+    /// Lines are 1-indexed. Any line past the end of the code is clamped to the end of the code.
+    pub fn from_lines(code: &str, start_line: usize, end_line: usize) -> Self {
+        Self::from_positions(
+            code,
+            (start_line, 1),
+            (end_line, usize::MAX),
+            ColumnEncoding::Utf8,
+        )
+    }
+
+    /// Creates a new formatting range between two positions in the code, given as 1-indexed `(line, column)` pairs.
+    /// Columns are measured in the units of the provided [`ColumnEncoding`].
+    /// A column pointing inside of a multi-byte character is moved back to the start of that character,
+    /// and a column past the end of a line is clamped to the end of the line (excluding the line ending).
+    pub fn from_positions(
+        code: &str,
+        start: (usize, usize),
+        end: (usize, usize),
+        encoding: ColumnEncoding,
+    ) -> Self {
+        Self {
+            start: Some(position_to_byte_offset(code, start, encoding)),
+            end: Some(position_to_byte_offset(code, end, encoding)),
+        }
+    }
+}
+
+/// The unit columns are measured in when creating a [`Range`] from line and column positions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColumnEncoding {
+    /// Columns are measured in bytes of the UTF-8 encoded code, as used by `git diff` and most terminal tools.
+    Utf8,
+    /// Columns are measured in UTF-16 code units, as used by the Language Server Protocol and many editors.
+    Utf16,
+}
+
+/// Converts a 1-indexed `(line, column)` position into a byte offset within the code
+fn position_to_byte_offset(
+    code: &str,
+    (line, column): (usize, usize),
+    encoding: ColumnEncoding,
+) -> usize {
+    let mut offset = 0;
+
+    for (line_number, line_contents) in code.split_inclusive('\n').enumerate() {
+        if line_number + 1 == line.max(1) {
+            let without_ending = line_contents.strip_suffix('\n').unwrap_or(line_contents);
+            let without_ending = without_ending.strip_suffix('\r').unwrap_or(without_ending);
+
+            let mut current_column = 1;
+            for (index, character) in without_ending.char_indices() {
+                let width = match encoding {
+                    ColumnEncoding::Utf8 => character.len_utf8(),
+                    ColumnEncoding::Utf16 => character.len_utf16(),
+                };
+
+                if current_column + width > column {
+                    return offset + index;
+                }
+                current_column += width;
+            }
+
+            return offset + without_ending.len();
+        }
+
+        offset += line_contents.len();
+    }
+
+    code.len()
 }
 
 /// The configuration to use when formatting.
@@ -95,6 +220,14 @@ pub struct Config {
     /// Whether to omit parentheses around function calls which take a single string literal or table.
     /// This is added for adoption reasons only, and is not recommended for new work.
     no_call_parentheses: bool,
+    /// The Lua syntax to parse code as. Formatting fails if this build of StyLua cannot parse the syntax.
+    syntax: LuaVersion,
+    /// Whether to convert the line endings within multiline comments and long strings to the configured line endings.
+    /// By default, the contents of these are kept as written.
+    normalise_multiline_line_endings: bool,
+    /// Luau: whether to align the types of fields within multiline type tables into a single column.
+    #[cfg_attr(not(feature = "luau"), allow(dead_code))]
+    align_type_fields: bool,
 }
 
 impl Config {
@@ -150,6 +283,30 @@ impl Config {
             ..self
         }
     }
+
+    /// Returns a new config with the given syntax
+    pub fn with_syntax(self, syntax: LuaVersion) -> Self {
+        Self { syntax, ..self }
+    }
+
+    /// Returns a new config with the given value for [`normalise_multiline_line_endings`]
+    pub fn with_normalise_multiline_line_endings(
+        self,
+        normalise_multiline_line_endings: bool,
+    ) -> Self {
+        Self {
+            normalise_multiline_line_endings,
+            ..self
+        }
+    }
+
+    /// Returns a new config with the given value for [`align_type_fields`]
+    pub fn with_align_type_fields(self, align_type_fields: bool) -> Self {
+        Self {
+            align_type_fields,
+            ..self
+        }
+    }
 }
 
 impl Default for Config {
@@ -161,6 +318,9 @@ impl Default for Config {
             indent_width: 4,
             quote_style: QuoteStyle::default(),
             no_call_parentheses: false,
+            syntax: LuaVersion::default(),
+            normalise_multiline_line_endings: false,
+            align_type_fields: false,
         }
     }
 }
@@ -174,20 +334,284 @@ pub enum OutputVerification {
     None,
 }
 
+/// The location of an error within the input code
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ErrorLocation {
+    line: usize,
+    column: usize,
+    start: usize,
+    end: usize,
+}
+
+impl ErrorLocation {
+    /// The line the error begins on, starting from 1
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The column the error begins on, starting from 1
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// The byte range of the input which the error spans, given as offsets from the beginning of the file
+    pub fn span(&self) -> std::ops::Range<usize> {
+        self.start..self.end
+    }
+
+    /// Finds the location of the provided full-moon error, if it refers to a position in the input
+    fn from_parse_error(error: &full_moon::Error) -> Option<Self> {
+        let (start, end) = match error {
+            full_moon::Error::AstError(full_moon::ast::AstError::UnexpectedToken {
+                token, ..
+            }) => (token.start_position(), token.end_position()),
+            full_moon::Error::TokenizerError(error) => (error.position(), error.position()),
+            _ => return None,
+        };
+
+        Some(Self {
+            line: start.line(),
+            column: start.character(),
+            start: start.bytes(),
+            end: end.bytes(),
+        })
+    }
+}
+
+/// An error that can occur when formatting code
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Error {
+    /// The input code could not be parsed
+    ParseError {
+        /// The error returned by full-moon when parsing
+        error: Box<full_moon::Error>,
+        /// Where in the input the error occurred, if known
+        location: Option<ErrorLocation>,
+    },
+    /// The formatted output could not be reparsed when verifying it.
+    /// This is an internal error, and should be reported.
+    VerificationAstError(Box<full_moon::Error>),
+    /// The formatted output was reparsed, but its AST differs to the input AST.
+    /// Code correctness may have changed.
+    VerificationAstDifference,
+    /// The syntax requested in the configuration cannot be parsed by this build of StyLua
+    UnsupportedSyntax(LuaVersion),
+    /// An inline `-- stylua:` configuration comment contained an unknown option or an invalid value
+    InlineConfigError {
+        /// A description of the problem with the comment
+        message: String,
+        /// The location of the comment within the input
+        location: ErrorLocation,
+    },
+}
+
+impl Error {
+    /// The location of the error within the input code, if known.
+    /// Only parse errors of the input code and invalid inline configuration comments have a location.
+    pub fn location(&self) -> Option<ErrorLocation> {
+        match self {
+            Error::ParseError { location, .. } => *location,
+            Error::InlineConfigError { location, .. } => Some(*location),
+            _ => None,
+        }
+    }
+
+    /// Creates a parse error from an error returned by full-moon when parsing the input code
+    fn from_parse_error(error: full_moon::Error) -> Self {
+        Error::ParseError {
+            location: ErrorLocation::from_parse_error(&error),
+            error: Box::new(error),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ParseError { error, .. } => write!(f, "error parsing: {}", error),
+            Error::VerificationAstError(error) => write!(
+                f,
+                "INTERNAL ERROR: Output AST generated a syntax error. Please report this at https://github.com/johnnymorganz/stylua/issues\n{}",
+                error
+            ),
+            Error::UnsupportedSyntax(syntax) => write!(
+                f,
+                "error: {:?} syntax is not supported by this build of StyLua, it must be built with the `{}` feature",
+                syntax,
+                syntax.required_feature().unwrap_or_default()
+            ),
+            Error::InlineConfigError { message, location } => write!(
+                f,
+                "error: invalid `-- stylua:` configuration comment on line {}: {}",
+                location.line, message
+            ),
+            Error::VerificationAstDifference => write!(
+                f,
+                "INTERNAL WARNING: Output AST may be different to input AST. Code correctness may have changed. Please examine the formatting diff and report any issues at https://github.com/johnnymorganz/stylua/issues"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 /// Formats given Lua code
 pub fn format_code(
     code: &str,
     config: Config,
     range: Option<Range>,
     verify_output: OutputVerification,
-) -> Result<String> {
-    let input_ast = match full_moon::parse(&code) {
-        Ok(ast) => ast,
-        Err(error) => {
-            return Err(format_err!("error parsing: {}", error));
+) -> Result<String, Error> {
+    format_code_with_plugins(code, config, range, &[], verify_output)
+}
+
+/// Formats given Lua code, running the hooks of the provided plugins around the formatting of nodes.
+/// Plugins are run in the order they are given. Otherwise, this behaves the same as [`format_code`].
+pub fn format_code_with_plugins(
+    code: &str,
+    config: Config,
+    range: Option<Range>,
+    plugins: &[Box<dyn FormatterPlugin>],
+    verify_output: OutputVerification,
+) -> Result<String, Error> {
+    match range {
+        Some(range) => format_code_internal(code, config, Some(&[range]), plugins, verify_output),
+        None => format_code_internal(code, config, None, plugins, verify_output),
+    }
+}
+
+/// Detects the line endings most commonly used within the code, given as one or more pieces of text.
+/// Unix line endings are preferred if both are used equally, or if the code contains no line endings.
+fn detect_line_endings<T: AsRef<str>>(code: impl IntoIterator<Item = T>) -> LineEndings {
+    let (line_feeds, carriage_returns) =
+        code.into_iter()
+            .fold((0, 0), |(line_feeds, carriage_returns), text| {
+                let text = text.as_ref();
+                (
+                    line_feeds + text.matches('\n').count(),
+                    carriage_returns + text.matches("\r\n").count(),
+                )
+            });
+
+    if carriage_returns > line_feeds - carriage_returns {
+        LineEndings::Windows
+    } else {
+        LineEndings::Unix
+    }
+}
+
+/// Formats the given full-moon [`Ast`](full_moon::ast::Ast), returning the formatted AST.
+/// This allows tools which have already parsed (and possibly transformed) code with full-moon to format it directly,
+/// without printing and reparsing the code. The AST must be created using the same version of full-moon as StyLua.
+/// Any inline configuration in the comments before the first token of the AST is applied, in the same way as `format_code`.
+pub fn format_ast(
+    input_ast: full_moon::ast::Ast,
+    config: Config,
+) -> Result<full_moon::ast::Ast, Error> {
+    // The header comments of the code are the leading trivia of its first token
+    let first_token = input_ast
+        .nodes()
+        .tokens()
+        .next()
+        .unwrap_or_else(|| input_ast.eof());
+    let header: String = first_token
+        .leading_trivia()
+        .map(ToString::to_string)
+        .collect();
+    let config = inline_config::apply_inline_config(&header, config)?;
+    syntax::check_syntax(&input_ast, config.syntax).map_err(Error::from_parse_error)?;
+
+    // Count the line endings within the tokens and their trivia, rather than printing the whole AST
+    let config = match config.line_endings {
+        LineEndings::Auto => {
+            let tokens = input_ast
+                .nodes()
+                .tokens()
+                .chain(std::iter::once(input_ast.eof()))
+                .flat_map(|token| {
+                    token
+                        .leading_trivia()
+                        .chain(std::iter::once(token.token()))
+                        .chain(token.trailing_trivia())
+                        .map(ToString::to_string)
+                });
+            config.with_line_endings(detect_line_endings(tokens))
         }
+        _ => config,
+    };
+
+    let code_formatter = formatters::CodeFormatter::new(config, None, &[]);
+    Ok(code_formatter.format(input_ast))
+}
+
+/// Sorts the given ranges by their start bound, merging together any ranges which overlap
+fn normalise_ranges(ranges: &[Range]) -> Vec<Range> {
+    let mut sorted_ranges = ranges.to_vec();
+    sorted_ranges.sort_by_key(|range| range.start.unwrap_or(0));
+
+    let mut merged_ranges: Vec<Range> = Vec::with_capacity(sorted_ranges.len());
+    for range in sorted_ranges {
+        match merged_ranges.last_mut() {
+            Some(previous)
+                if previous
+                    .end
+                    .map_or(true, |end| range.start.unwrap_or(0) <= end) =>
+            {
+                previous.end = match (previous.end, range.end) {
+                    (Some(previous_end), Some(end)) => Some(previous_end.max(end)),
+                    _ => None,
+                };
+            }
+            _ => merged_ranges.push(range),
+        }
+    }
+
+    merged_ranges
+}
+
+/// Formats given Lua code, only formatting content within any of the provided ranges.
+/// All of the ranges are formatted in a single pass over the code. They do not need to be sorted, and may overlap.
+/// If no ranges are provided, the code is left unchanged.
+/// Any `-- stylua: key=value` configuration comments at the top of the code override the provided config.
+pub fn format_code_ranges(
+    code: &str,
+    config: Config,
+    ranges: &[Range],
+    verify_output: OutputVerification,
+) -> Result<String, Error> {
+    format_code_internal(code, config, Some(ranges), &[], verify_output)
+}
+
+/// Parses the given Lua code, checking that it only uses syntax belonging to the Lua version
+fn parse_code(code: &str, syntax: LuaVersion) -> Result<full_moon::ast::Ast, Error> {
+    let ast = full_moon::parse(code).map_err(Error::from_parse_error)?;
+    syntax::check_syntax(&ast, syntax).map_err(Error::from_parse_error)?;
+    Ok(ast)
+}
+
+/// Formats the given Lua code within the ranges, or the whole code if no ranges are given,
+/// running any plugins around the formatting of nodes
+fn format_code_internal(
+    code: &str,
+    config: Config,
+    ranges: Option<&[Range]>,
+    plugins: &[Box<dyn FormatterPlugin>],
+    verify_output: OutputVerification,
+) -> Result<String, Error> {
+    let config = inline_config::apply_inline_config(code, config)?;
+
+    if !config.syntax.is_supported() {
+        return Err(Error::UnsupportedSyntax(config.syntax));
+    }
+
+    let config = match config.line_endings {
+        LineEndings::Auto => config.with_line_endings(detect_line_endings(std::iter::once(code))),
+        _ => config,
     };
 
+    let input_ast = parse_code(code, config.syntax)?;
+
     // Clone the input AST only if we are verifying, to later use for checking
     let input_ast_for_verification = if let OutputVerification::Full = verify_output {
         Some(input_ast.to_owned())
@@ -195,7 +619,7 @@ pub fn format_code(
         None
     };
 
-    let code_formatter = formatters::CodeFormatter::new(config, range);
+    let code_formatter = formatters::CodeFormatter::new(config, ranges, plugins);
     let ast = code_formatter.format(input_ast);
     let output = full_moon::print(&ast);
 
@@ -203,21 +627,67 @@ pub fn format_code(
     if let Some(input_ast) = input_ast_for_verification {
         let reparsed_output = match full_moon::parse(&output) {
             Ok(ast) => ast,
-            Err(error) => {
-                return Err(format_err!(
-                "INTERNAL ERROR: Output AST generated a syntax error. Please report this at https://github.com/johnnymorganz/stylua/issues\n{}",
-                error
-            ))
-            }
+            Err(error) => return Err(Error::VerificationAstError(Box::new(error))),
         };
 
         let mut ast_verifier = verify_ast::AstVerifier::new();
         if !ast_verifier.compare(input_ast, reparsed_output) {
-            return Err(format_err!(
-                "INTERNAL WARNING: Output AST may be different to input AST. Code correctness may have changed. Please examine the formatting diff and report any issues at https://github.com/johnnymorganz/stylua/issues"
-            ));
+            return Err(Error::VerificationAstDifference);
         }
     }
 
     Ok(output)
 }
+
+/// Formats given Lua code, returning the changes made as a list of text edits rather than the formatted output.
+/// Each edit is a byte range within the original code, given as offsets from the beginning of the file, along with
+/// the text to replace it with. The edits do not overlap, and are sorted in the order they appear within the code.
+/// Applying every edit to the original code produces the same output as [`format_code`].
+pub fn format_code_edits(
+    code: &str,
+    config: Config,
+    range: Option<Range>,
+    verify_output: OutputVerification,
+) -> Result<Vec<(std::ops::Range<usize>, String)>, Error> {
+    let output = format_code(code, config, range, verify_output)?;
+
+    // The input has already been successfully parsed, but the output is only reparsed when verifying it.
+    // If the output cannot be tokenized, the formatter has generated invalid code
+    let input_tokens = token_strings(code).map_err(Error::from_parse_error)?;
+    let output_tokens =
+        token_strings(&output).map_err(|error| Error::VerificationAstError(Box::new(error)))?;
+
+    let input_offsets = token_offsets(&input_tokens);
+    let output_offsets = token_offsets(&output_tokens);
+
+    let edits =
+        similar::capture_diff_slices(similar::Algorithm::Myers, &input_tokens, &output_tokens)
+            .iter()
+            .filter_map(|op| match op.as_tag_tuple() {
+                (similar::DiffTag::Equal, _, _) => None,
+                (_, input_range, output_range) => Some((
+                    input_offsets[input_range.start]..input_offsets[input_range.end],
+                    output[output_offsets[output_range.start]..output_offsets[output_range.end]]
+                        .to_string(),
+                )),
+            })
+            .collect();
+
+    Ok(edits)
+}
+
+/// Splits the given code into the text of each of its tokens
+fn token_strings(code: &str) -> Result<Vec<String>, full_moon::Error> {
+    let tokens = full_moon::tokenizer::tokens(code).map_err(full_moon::Error::TokenizerError)?;
+    Ok(tokens.iter().map(|token| token.to_string()).collect())
+}
+
+/// Finds the byte offset of the start of each token, with an extra offset at the end for the total length
+fn token_offsets(tokens: &[String]) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(tokens.iter().scan(0, |offset, token| {
+            *offset += token.len();
+            Some(*offset)
+        }))
+        .collect()
+}
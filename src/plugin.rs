@@ -0,0 +1,120 @@
+use crate::{context::Context, shape::Shape};
+use full_moon::ast::{Expression, Stmt, TableConstructor};
+use std::borrow::Cow;
+
+/// A plugin which hooks into the formatter to apply custom formatting rules.
+///
+/// Each hook is provided the formatting [`Context`] and the [`Shape`] the node is being formatted at.
+/// `before_*` hooks are called with the node before StyLua formats it, and can transform the input,
+/// whilst `after_*` hooks are called with StyLua's formatted output. Hooks are only called for nodes which
+/// are going to be formatted, and by default leave the node unchanged.
+/// When multiple plugins are provided, their hooks are run in the order the plugins were given.
+///
+/// StyLua may try several layouts of an expression, such as formatting it normally or hanging it, to find the best output.
+/// The `before` hook is run once, with every layout created from its result, and the `after` hook is only run on the
+/// layout which is kept. Nodes contained within the expression are formatted as part of each layout, so their hooks
+/// may be run more than once.
+pub trait FormatterPlugin {
+    /// Called before a statement is formatted
+    fn before_format_stmt(&self, _ctx: &Context, stmt: Stmt, _shape: Shape) -> Stmt {
+        stmt
+    }
+
+    /// Called after a statement has been formatted
+    fn after_format_stmt(&self, _ctx: &Context, stmt: Stmt, _shape: Shape) -> Stmt {
+        stmt
+    }
+
+    /// Called before an expression is formatted
+    fn before_format_expression(
+        &self,
+        _ctx: &Context,
+        expression: Expression,
+        _shape: Shape,
+    ) -> Expression {
+        expression
+    }
+
+    /// Called after an expression has been formatted
+    fn after_format_expression(
+        &self,
+        _ctx: &Context,
+        expression: Expression,
+        _shape: Shape,
+    ) -> Expression {
+        expression
+    }
+
+    /// Called before a table constructor is formatted
+    fn before_format_table_constructor(
+        &self,
+        _ctx: &Context,
+        table_constructor: TableConstructor,
+        _shape: Shape,
+    ) -> TableConstructor {
+        table_constructor
+    }
+
+    /// Called after a table constructor has been formatted
+    fn after_format_table_constructor(
+        &self,
+        _ctx: &Context,
+        table_constructor: TableConstructor,
+        _shape: Shape,
+    ) -> TableConstructor {
+        table_constructor
+    }
+}
+
+/// Runs the `before` hook of every plugin on a node which is about to be formatted.
+/// If there are no plugins, the node is borrowed rather than cloned.
+pub(crate) fn run_before_hooks<'n, T, B>(
+    ctx: &Context,
+    node: &'n T,
+    shape: Shape,
+    before: B,
+) -> Cow<'n, T>
+where
+    T: Clone,
+    B: Fn(&dyn FormatterPlugin, &Context, T, Shape) -> T,
+{
+    let plugins = ctx.plugins();
+    if plugins.is_empty() {
+        return Cow::Borrowed(node);
+    }
+
+    Cow::Owned(plugins.iter().fold(node.to_owned(), |node, plugin| {
+        before(plugin.as_ref(), ctx, node, shape)
+    }))
+}
+
+/// Runs the `after` hook of every plugin on a node which has been formatted
+pub(crate) fn run_after_hooks<T, A>(ctx: &Context, node: T, shape: Shape, after: A) -> T
+where
+    A: Fn(&dyn FormatterPlugin, &Context, T, Shape) -> T,
+{
+    ctx.plugins().iter().fold(node, |node, plugin| {
+        after(plugin.as_ref(), ctx, node, shape)
+    })
+}
+
+/// Formats a node using the provided formatter, running the `before` and `after` hooks of every plugin around it.
+/// If there are no plugins, the node is formatted directly without being cloned.
+pub(crate) fn format_with_plugins<T, B, A, F>(
+    ctx: &Context,
+    node: &T,
+    shape: Shape,
+    before: B,
+    after: A,
+    formatter: F,
+) -> T
+where
+    T: Clone,
+    B: Fn(&dyn FormatterPlugin, &Context, T, Shape) -> T,
+    A: Fn(&dyn FormatterPlugin, &Context, T, Shape) -> T,
+    F: FnOnce(&Context, &T, Shape) -> T,
+{
+    let node = run_before_hooks(ctx, node, shape, before);
+    let formatted = formatter(ctx, &node, shape);
+    run_after_hooks(ctx, formatted, shape, after)
+}
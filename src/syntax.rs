@@ -0,0 +1,116 @@
+use crate::LuaVersion;
+use full_moon::{
+    ast::{Ast, AstError},
+    node::Node,
+    visitors::Visitor,
+};
+
+#[cfg(feature = "lua52")]
+use full_moon::ast::lua52::{Goto, Label};
+#[cfg(feature = "luau")]
+use full_moon::ast::{
+    types::{
+        CompoundAssignment, ExportedTypeDeclaration, GenericDeclaration, TypeAssertion,
+        TypeDeclaration, TypeSpecifier,
+    },
+    LastStmt,
+};
+
+/// Checks that an AST only uses the syntax of a single Lua version.
+/// The parser is chosen when StyLua is compiled, so it accepts the syntax of every enabled version at once.
+struct SyntaxChecker {
+    syntax: LuaVersion,
+    /// The error for the first node found which is not part of the syntax
+    error: Option<AstError>,
+}
+
+impl SyntaxChecker {
+    /// Records an error for the node if it uses syntax from a version other than the one being checked
+    #[cfg_attr(not(any(feature = "luau", feature = "lua52")), allow(dead_code))]
+    fn check(&mut self, node: &impl Node, description: &str, node_syntax: LuaVersion) {
+        if self.error.is_some() || self.syntax == node_syntax {
+            return;
+        }
+
+        if let Some(token) = node.tokens().next() {
+            self.error = Some(AstError::UnexpectedToken {
+                token: token.token().to_owned(),
+                additional: Some(
+                    format!(
+                        "{} are {:?} syntax, which cannot be used when the syntax is set to {:?}",
+                        description, node_syntax, self.syntax
+                    )
+                    .into(),
+                ),
+            });
+        }
+    }
+}
+
+impl Visitor for SyntaxChecker {
+    #[cfg(feature = "luau")]
+    fn visit_last_stmt(&mut self, last_stmt: &LastStmt) {
+        if let LastStmt::Continue(_) = last_stmt {
+            self.check(last_stmt, "`continue` statements", LuaVersion::Luau);
+        }
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_compound_assignment(&mut self, node: &CompoundAssignment) {
+        self.check(node, "compound assignments", LuaVersion::Luau);
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_exported_type_declaration(&mut self, node: &ExportedTypeDeclaration) {
+        self.check(node, "type declarations", LuaVersion::Luau);
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_generic_declaration(&mut self, node: &GenericDeclaration) {
+        self.check(node, "generic type parameters", LuaVersion::Luau);
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_assertion(&mut self, node: &TypeAssertion) {
+        self.check(node, "type assertions", LuaVersion::Luau);
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_declaration(&mut self, node: &TypeDeclaration) {
+        self.check(node, "type declarations", LuaVersion::Luau);
+    }
+
+    #[cfg(feature = "luau")]
+    fn visit_type_specifier(&mut self, node: &TypeSpecifier) {
+        self.check(node, "type annotations", LuaVersion::Luau);
+    }
+
+    #[cfg(feature = "lua52")]
+    fn visit_goto(&mut self, node: &Goto) {
+        self.check(node, "`goto` statements", LuaVersion::Lua52);
+    }
+
+    #[cfg(feature = "lua52")]
+    fn visit_label(&mut self, node: &Label) {
+        self.check(node, "labels", LuaVersion::Lua52);
+    }
+}
+
+/// Checks that the AST only uses syntax belonging to the given Lua version, returning an error for the first node
+/// which does not. Any syntax is allowed when the version is `All`.
+pub fn check_syntax(ast: &Ast, syntax: LuaVersion) -> Result<(), full_moon::Error> {
+    if syntax == LuaVersion::All {
+        return Ok(());
+    }
+
+    let mut checker = SyntaxChecker {
+        syntax,
+        error: None,
+    };
+    checker.visit_ast(ast);
+
+    match checker.error {
+        Some(error) => Err(full_moon::Error::AstError(error)),
+        None => Ok(()),
+    }
+}
@@ -41,6 +41,28 @@ fn remove_parentheses(expression: Expression) -> Expression {
     }
 }
 
+/// Normalises the text of a number literal, so that equal numbers written differently compare as the same.
+/// Any literal which cannot be interpreted is compared using its original text, rather than panicking.
+fn normalise_number(text: &str) -> String {
+    // Luau: cleanse number of any digit separators
+    #[cfg(feature = "luau")]
+    let text = text.replace("_", "");
+
+    let text = text.to_lowercase();
+
+    let value = if let Some(hex) = text.strip_prefix("0x") {
+        u64::from_str_radix(hex, 16).ok().map(|num| num.to_string())
+    } else if let Some(binary) = text.strip_prefix("0b") {
+        u64::from_str_radix(binary, 2)
+            .ok()
+            .map(|num| num.to_string())
+    } else {
+        text.parse::<f64>().ok().map(|num| num.to_string())
+    };
+
+    value.unwrap_or(text)
+}
+
 // Massages the AST so that structures we have changed in Nodes remain constant.
 // Note, the massaged AST may not actually be valid syntax if we print it back out, but we have already checked
 // the validity of the output, so any invalid syntax output would already have been flagged.
@@ -121,30 +143,13 @@ impl VisitorMut for AstVerifier {
 
     fn visit_number(&mut self, token: Token) -> Token {
         // We change the formatting of number literals
-        // We will normalise all numbers by running `str::parse(number).to_string()` and replacing the Token with this output.
+        // We will normalise all numbers by parsing them and replacing the Token with the parsed value.
         // This will help highlight any differences, as it would lead to a different parsed output
 
         let token_type = match token.token_type() {
-            TokenType::Number { text } => {
-                // Luau: cleanse number of any digit separators
-                #[cfg(feature = "luau")]
-                let text = text.replace("_", "");
-
-                let number = match text.as_str().parse::<f64>() {
-                    Ok(num) => num,
-                    Err(_) => match i32::from_str_radix(&text.as_str()[2..], 16) {
-                        Ok(num) => num.into(),
-                        Err(_) => match i32::from_str_radix(&text.as_str()[2..], 2) {
-                            Ok(num) => num.into(),
-                            Err(_) => unreachable!(),
-                        },
-                    },
-                };
-
-                TokenType::Number {
-                    text: number.to_string().into(),
-                }
-            }
+            TokenType::Number { text } => TokenType::Number {
+                text: normalise_number(text.as_str()).into(),
+            },
             _ => unreachable!(),
         };
 
@@ -154,13 +159,18 @@ impl VisitorMut for AstVerifier {
     fn visit_string_literal(&mut self, token: Token) -> Token {
         // We change the string quotes of our progrem.
         // Convert all string literals to brackets quotes, and remove any escapes.
+        // Line endings within long strings may also be converted, but are read the same by Lua, so are normalised.
         let token_type = match token.token_type() {
             TokenType::StringLiteral {
                 literal,
                 multi_line,
                 ..
             } => TokenType::StringLiteral {
-                literal: literal.to_owned().replace("\\", "").into(),
+                literal: literal
+                    .to_owned()
+                    .replace("\\", "")
+                    .replace("\r\n", "\n")
+                    .into(),
                 multi_line: multi_line.to_owned(),
                 quote_type: StringLiteralQuoteType::Brackets,
             },
@@ -0,0 +1,3 @@
+local function getIntrospectionType(name: string): IntrospectionScalarType | IntrospectionObjectType | IntrospectionInterfaceType | IntrospectionUnionType | IntrospectionEnumType
+	return types[name]
+end
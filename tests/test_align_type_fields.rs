@@ -0,0 +1,28 @@
+#![cfg(feature = "luau")]
+use stylua_lib::{format_code, Config, OutputVerification};
+
+fn format(input: &str) -> String {
+    format_code(
+        input,
+        Config::default().with_align_type_fields(true),
+        None,
+        OutputVerification::None,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_align_type_fields() {
+    assert_eq!(
+        format("export type Foo = {\n\tid: number,\n\tdisplayName: string,\n\t-- the owner\n\towner: Player?,\n}\n"),
+        "export type Foo = {\n\tid:          number,\n\tdisplayName: string,\n\t-- the owner\n\towner:       Player?,\n}\n"
+    );
+}
+
+#[test]
+fn test_singleline_type_table_not_aligned() {
+    assert_eq!(
+        format("type Foo = { id: number, displayName: string }\n"),
+        "type Foo = { id: number, displayName: string }\n"
+    );
+}
@@ -0,0 +1,49 @@
+use stylua_lib::{format_code, format_code_edits, Config, OutputVerification};
+
+fn apply_edits(input: &str, edits: &[(std::ops::Range<usize>, String)]) -> String {
+    let mut output = input.to_string();
+    // Apply in reverse, so that earlier byte offsets remain valid
+    for (range, replacement) in edits.iter().rev() {
+        output.replace_range(range.to_owned(), replacement);
+    }
+    output
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_edits_match_formatted_output() {
+    let input = r###"
+local foo     =      bar
+local   x = {1,2,3}
+-- keep this comment
+if x then print( "hello" ) end
+"###;
+
+    let edits =
+        format_code_edits(input, Config::default(), None, OutputVerification::None).unwrap();
+    let formatted = format_code(input, Config::default(), None, OutputVerification::None).unwrap();
+
+    assert_eq!(apply_edits(input, &edits), formatted);
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_no_edits_when_formatted() {
+    let input = "local foo = bar\nlocal baz = { 1, 2, 3 }\n";
+
+    let edits =
+        format_code_edits(input, Config::default(), None, OutputVerification::None).unwrap();
+
+    assert!(edits.is_empty());
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_edits_are_minimal() {
+    let input = "local foo = bar\nlocal   baz = 1\n";
+
+    let edits =
+        format_code_edits(input, Config::default(), None, OutputVerification::None).unwrap();
+
+    assert_eq!(edits, vec![(21..24, String::from(" "))]);
+}
@@ -0,0 +1,39 @@
+use stylua_lib::{format_code, Config, Error, LuaVersion, OutputVerification};
+
+fn format(input: &str) -> Result<String, Error> {
+    format_code(input, Config::default(), None, OutputVerification::None)
+}
+
+#[test]
+fn test_parse_error_location() {
+    let error = format("local x = 1\n)").unwrap_err();
+    assert!(matches!(error, Error::ParseError { .. }));
+
+    let location = error.location().unwrap();
+    assert_eq!(location.line(), 2);
+    assert_eq!(location.column(), 1);
+    assert_eq!(location.span(), 12..13);
+}
+
+#[test]
+fn test_parse_error_message() {
+    let error = format("local x = 1\n)").unwrap_err();
+    assert!(error.to_string().starts_with("error parsing: "));
+}
+
+#[test]
+#[cfg(not(feature = "luau"))]
+fn test_unsupported_syntax() {
+    let config = Config::default().with_syntax(LuaVersion::Luau);
+    let error = format_code("local x = 1", config, None, OutputVerification::None).unwrap_err();
+    assert!(matches!(error, Error::UnsupportedSyntax(LuaVersion::Luau)));
+}
+
+#[test]
+fn test_verify_large_hex_number() {
+    let code = "local x = 0xFFFFFFFFFF\n";
+    assert_eq!(
+        format_code(code, Config::default(), None, OutputVerification::Full).unwrap(),
+        code
+    );
+}
@@ -0,0 +1,24 @@
+use stylua_lib::{format_ast, format_code, Config, LineEndings, OutputVerification};
+
+#[test]
+fn test_format_ast() {
+    let code = "local   x   =   1\nprint( x )\n";
+    let ast = full_moon::parse(code).unwrap();
+
+    assert_eq!(
+        full_moon::print(&format_ast(ast, Config::default()).unwrap()),
+        format_code(code, Config::default(), None, OutputVerification::None).unwrap()
+    );
+}
+
+#[test]
+fn test_format_ast_auto_line_endings() {
+    let code = "-- header\r\nlocal   x   =   1\r\nprint( x )\n";
+    let ast = full_moon::parse(code).unwrap();
+    let config = Config::default().with_line_endings(LineEndings::Auto);
+
+    assert_eq!(
+        full_moon::print(&format_ast(ast, config).unwrap()),
+        "-- header\r\nlocal x = 1\r\nprint(x)\r\n"
+    );
+}
@@ -0,0 +1,90 @@
+use stylua_lib::{format_ast, format_code, Config, Error, LuaVersion, OutputVerification};
+
+fn format(input: &str) -> Result<String, Error> {
+    format_code(input, Config::default(), None, OutputVerification::None)
+}
+
+#[test]
+fn test_inline_config_overrides_config() {
+    assert_eq!(
+        format("-- stylua: quote_style=ForceSingle, indent_type=Spaces, indent_width=2\ndo\nlocal x = \"foo\"\nend\n")
+            .unwrap(),
+        "-- stylua: quote_style=ForceSingle, indent_type=Spaces, indent_width=2\ndo\n  local x = 'foo'\nend\n"
+    );
+}
+
+#[test]
+fn test_inline_config_only_in_header() {
+    let code = "local x = \"foo\"\n-- stylua: quote_style=ForceSingle\nlocal y = \"bar\"\n";
+    assert_eq!(format(code).unwrap(), code);
+}
+
+#[test]
+fn test_inline_config_ignore_comment() {
+    let code = "-- stylua: ignore\nlocal   x = 1\n";
+    assert_eq!(format(code).unwrap(), code);
+}
+
+#[test]
+fn test_inline_config_prose_comment() {
+    let code = "-- stylua: this file is generated, do not edit\nlocal x = 1\n";
+    assert_eq!(format(code).unwrap(), code);
+}
+
+#[test]
+fn test_inline_config_unknown_option() {
+    let error = format("-- header\n-- stylua: colum_width=80\nlocal x = 1\n").unwrap_err();
+    assert!(matches!(error, Error::InlineConfigError { .. }));
+
+    let location = error.location().unwrap();
+    assert_eq!(location.line(), 2);
+    assert_eq!(location.span(), 10..35);
+}
+
+#[test]
+fn test_inline_config_invalid_value() {
+    let error = format("-- stylua: column_width=wide\nlocal x = 1\n").unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("invalid value `wide` for option `column_width`"));
+}
+
+#[test]
+fn test_syntax_header_alias() {
+    let code = "-- !syntax Luau\nlocal x = 1\n";
+    if LuaVersion::Luau.is_supported() {
+        assert_eq!(format(code).unwrap(), code);
+    } else {
+        assert!(matches!(
+            format(code).unwrap_err(),
+            Error::UnsupportedSyntax(LuaVersion::Luau)
+        ));
+    }
+
+    // The header takes precedence over the provided config, in the same way as other inline configuration
+    let code = "-- !syntax Lua51\nlocal x = 1\n";
+    let config = Config::default().with_syntax(LuaVersion::Luau);
+    assert_eq!(
+        format_code(code, config, None, OutputVerification::None).unwrap(),
+        code
+    );
+}
+
+#[test]
+fn test_syntax_header_unknown_syntax() {
+    let error = format("-- !syntax Lua99\nlocal x = 1\n").unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("invalid value `Lua99` for option `syntax`"));
+}
+
+#[test]
+fn test_inline_config_format_ast() {
+    let code = "-- stylua: quote_style=ForceSingle\nlocal x = \"foo\"\n";
+    let ast = full_moon::parse(code).unwrap();
+
+    assert_eq!(
+        full_moon::print(&format_ast(ast, Config::default()).unwrap()),
+        "-- stylua: quote_style=ForceSingle\nlocal x = 'foo'\n"
+    );
+}
@@ -0,0 +1,43 @@
+use stylua_lib::{format_code, Config, LineEndings, OutputVerification};
+
+fn format(input: &str, config: Config) -> String {
+    format_code(input, config, None, OutputVerification::Full).unwrap()
+}
+
+#[test]
+fn test_auto_detects_windows_line_endings() {
+    let config = Config::default().with_line_endings(LineEndings::Auto);
+    assert_eq!(
+        format("local x   = 1\r\nlocal y = 2\r\nlocal z = 3\n", config),
+        "local x = 1\r\nlocal y = 2\r\nlocal z = 3\r\n"
+    );
+}
+
+#[test]
+fn test_auto_detects_unix_line_endings() {
+    let config = Config::default().with_line_endings(LineEndings::Auto);
+    assert_eq!(
+        format("local x   = 1\nlocal y = 2\r\nlocal z = 3\n", config),
+        "local x = 1\nlocal y = 2\nlocal z = 3\n"
+    );
+}
+
+#[test]
+fn test_multiline_line_endings_kept_by_default() {
+    let config = Config::default().with_line_endings(LineEndings::Unix);
+    assert_eq!(
+        format("--[[\r\ncomment\r\n]]\nlocal x = [[\r\nfoo\r\n]]\n", config),
+        "--[[\r\ncomment\r\n]]\nlocal x = [[\r\nfoo\r\n]]\n"
+    );
+}
+
+#[test]
+fn test_normalise_multiline_line_endings() {
+    let config = Config::default()
+        .with_line_endings(LineEndings::Windows)
+        .with_normalise_multiline_line_endings(true);
+    assert_eq!(
+        format("--[[\ncomment\r\n]]\nlocal x = [[\nfoo\n]]\n", config),
+        "--[[\r\ncomment\r\n]]\r\nlocal x = [[\r\nfoo\r\n]]\r\n"
+    );
+}
@@ -0,0 +1,157 @@
+use full_moon::{
+    ast::{Expression, Stmt, Value},
+    tokenizer::{Symbol, Token, TokenReference, TokenType},
+};
+use std::{cell::Cell, rc::Rc};
+use stylua_lib::{
+    format_code, format_code_with_plugins, CodeFormatter, Config, Context, FormatterPlugin,
+    OutputVerification, Range, Shape,
+};
+
+/// Counts the number of nodes each hook is called with, leaving the nodes unchanged
+#[derive(Default)]
+struct CountingPlugin {
+    statements: Rc<Cell<usize>>,
+    expressions: Rc<Cell<usize>>,
+}
+
+impl FormatterPlugin for CountingPlugin {
+    fn before_format_stmt(&self, _ctx: &Context, stmt: Stmt, _shape: Shape) -> Stmt {
+        self.statements.set(self.statements.get() + 1);
+        stmt
+    }
+
+    fn after_format_expression(
+        &self,
+        _ctx: &Context,
+        expression: Expression,
+        _shape: Shape,
+    ) -> Expression {
+        self.expressions.set(self.expressions.get() + 1);
+        expression
+    }
+}
+
+#[test]
+fn test_plugin_hooks() {
+    let code = "local x   = 1\nlocal y =   2\ndo\n    local z = 3\nend\n";
+
+    let plugin = CountingPlugin::default();
+    let statements = plugin.statements.clone();
+    let expressions = plugin.expressions.clone();
+    let plugins: Vec<Box<dyn FormatterPlugin>> = vec![Box::new(plugin)];
+
+    let formatter = CodeFormatter::new(Config::default(), None, &plugins);
+    let output = full_moon::print(&formatter.format(full_moon::parse(code).unwrap()));
+
+    assert_eq!(
+        output,
+        format_code(code, Config::default(), None, OutputVerification::None).unwrap()
+    );
+    assert_eq!(statements.get(), 4);
+    assert_eq!(expressions.get(), 3);
+}
+
+#[test]
+fn test_format_code_with_plugins_range() {
+    let code = "local x   = 1\nlocal y =   2\n";
+
+    let plugin = CountingPlugin::default();
+    let statements = plugin.statements.clone();
+    let plugins: Vec<Box<dyn FormatterPlugin>> = vec![Box::new(plugin)];
+
+    let output = format_code_with_plugins(
+        code,
+        Config::default(),
+        Some(Range::from_values(Some(14), None)),
+        &plugins,
+        OutputVerification::Full,
+    )
+    .unwrap();
+
+    assert_eq!(output, "local x   = 1\nlocal y = 2\n");
+    assert_eq!(statements.get(), 1);
+}
+
+/// Replaces `true` with `false`, counting the binary operator expressions each hook is called with
+#[derive(Default)]
+struct ReplaceTruePlugin {
+    before_binops: Rc<Cell<usize>>,
+    after_binops: Rc<Cell<usize>>,
+}
+
+impl FormatterPlugin for ReplaceTruePlugin {
+    fn before_format_expression(
+        &self,
+        _ctx: &Context,
+        mut expression: Expression,
+        _shape: Shape,
+    ) -> Expression {
+        match expression {
+            Expression::BinaryOperator { .. } => {
+                self.before_binops.set(self.before_binops.get() + 1)
+            }
+            Expression::Value { ref mut value, .. } => {
+                if let Value::Symbol(token) = &**value {
+                    if let TokenType::Symbol {
+                        symbol: Symbol::True,
+                    } = token.token_type()
+                    {
+                        let token = TokenReference::new(
+                            token.leading_trivia().cloned().collect(),
+                            Token::new(TokenType::Symbol {
+                                symbol: Symbol::False,
+                            }),
+                            token.trailing_trivia().cloned().collect(),
+                        );
+                        **value = Value::Symbol(token);
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        expression
+    }
+
+    fn after_format_expression(
+        &self,
+        _ctx: &Context,
+        expression: Expression,
+        _shape: Shape,
+    ) -> Expression {
+        if let Expression::BinaryOperator { .. } = expression {
+            self.after_binops.set(self.after_binops.get() + 1);
+        }
+        expression
+    }
+}
+
+#[test]
+fn test_plugin_hooks_hanging_expression() {
+    let code = "local x = true and some_long_function_name(first_argument, second_argument, third_argument, fourth_argument, fifth_argument)\n";
+
+    let plugin = ReplaceTruePlugin::default();
+    let before_binops = plugin.before_binops.clone();
+    let after_binops = plugin.after_binops.clone();
+    let plugins: Vec<Box<dyn FormatterPlugin>> = vec![Box::new(plugin)];
+
+    let output = format_code_with_plugins(
+        code,
+        Config::default(),
+        None,
+        &plugins,
+        OutputVerification::None,
+    )
+    .unwrap();
+
+    // The expression is hung, with the plugin still replacing the value within it
+    assert_eq!(
+        output,
+        "local x = false\n\tand some_long_function_name(first_argument, second_argument, third_argument, fourth_argument, fifth_argument)\n"
+    );
+
+    // The expression is laid out several ways, but the hooks are only run once for it
+    assert_eq!(before_binops.get(), 1);
+    assert_eq!(after_binops.get(), 1);
+}
@@ -1,4 +1,6 @@
-use stylua_lib::{format_code, Config, OutputVerification, Range};
+use stylua_lib::{
+    format_code, format_code_ranges, ColumnEncoding, Config, OutputVerification, Range,
+};
 
 fn format(input: &str, range: Range) -> String {
     format_code(
@@ -167,3 +169,117 @@ end end end end end
                 
     "###);
 }
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_from_lines() {
+    let code = "local   a = 1\nlocal   b = 2\nlocal   c = 3\n";
+    assert_eq!(
+        format(code, Range::from_lines(code, 2, 2)),
+        "local   a = 1\nlocal b = 2\nlocal   c = 3\n"
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_from_lines_past_end_of_file() {
+    let code = "local   a = 1\nlocal   b = 2\n";
+    assert_eq!(
+        format(code, Range::from_lines(code, 2, 100)),
+        "local   a = 1\nlocal b = 2\n"
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_from_positions_utf16_columns() {
+    // `€` is one UTF-16 code unit but three UTF-8 bytes, so column 16 is the end of the first line
+    let code = "local  s = \"€€\"\nlocal   x = 1\n";
+    assert_eq!(
+        format(
+            code,
+            Range::from_positions(code, (1, 1), (1, 16), ColumnEncoding::Utf16)
+        ),
+        "local s = \"€€\"\nlocal   x = 1\n"
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_from_positions_utf8_columns() {
+    // Column 16 falls within the second `€` when measured in bytes, so the statement is not fully within the range
+    let code = "local  s = \"€€\"\nlocal   x = 1\n";
+    assert_eq!(
+        format(
+            code,
+            Range::from_positions(code, (1, 1), (1, 16), ColumnEncoding::Utf8)
+        ),
+        code
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_multiple_ranges() {
+    let code = "local   a = 1\nlocal   b = 2\nlocal   c = 3\nlocal   d = 4\n";
+    assert_eq!(
+        format_code_ranges(
+            code,
+            Config::default(),
+            &[Range::from_lines(code, 4, 4), Range::from_lines(code, 1, 2)],
+            OutputVerification::None
+        )
+        .unwrap(),
+        "local a = 1\nlocal b = 2\nlocal   c = 3\nlocal d = 4\n"
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_no_ranges() {
+    let code = "local   a = 1\nlocal   b = 2\n";
+    assert_eq!(
+        format_code_ranges(code, Config::default(), &[], OutputVerification::None).unwrap(),
+        code
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_semicolons_outside_range_kept() {
+    let code = "local a = 1; -- comment\nlocal b = (a)\n(foo)()\nlocal   c = 3;\nreturn a;\n";
+    assert_eq!(
+        format_code_ranges(code, Config::default(), &[], OutputVerification::None).unwrap(),
+        code
+    );
+    assert_eq!(
+        format_code(
+            code,
+            Config::default(),
+            Some(Range::from_lines(code, 4, 4)),
+            OutputVerification::None
+        )
+        .unwrap(),
+        "local a = 1; -- comment\nlocal b = (a)\n(foo)()\nlocal c = 3\nreturn a;\n"
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_partial_function_body() {
+    let code = "local function foo()\n    local   x = 1\n    local   y = 2\nend\n";
+    assert_eq!(
+        format(code, Range::from_lines(code, 2, 2)),
+        "local function foo()\n\tlocal x = 1\n    local   y = 2\nend\n"
+    );
+}
+
+#[test]
+#[cfg_attr(feature = "luau", ignore)]
+fn test_partial_else_block() {
+    let code = "if x then\n  a()\nelse\n  local   b  =  2\nend\n";
+    assert_eq!(
+        format(code, Range::from_lines(code, 4, 4)),
+        "if x then\n  a()\nelse\n\tlocal b = 2\nend\n"
+    );
+}
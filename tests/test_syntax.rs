@@ -0,0 +1,89 @@
+use stylua_lib::{format_code, Config, Error, LuaVersion, OutputVerification};
+
+fn format(input: &str, syntax: LuaVersion) -> Result<String, Error> {
+    format_code(
+        input,
+        Config::default().with_syntax(syntax),
+        None,
+        OutputVerification::None,
+    )
+}
+
+#[test]
+#[cfg(feature = "luau")]
+fn test_luau_syntax_rejected_as_lua51() {
+    let code = "local x: number   = 1\n";
+    let error = format(code, LuaVersion::Lua51).unwrap_err();
+    assert!(matches!(error, Error::ParseError { .. }));
+    assert!(error.to_string().contains(
+        "type annotations are Luau syntax, which cannot be used when the syntax is set to Lua51"
+    ));
+
+    // The error points at the start of the type annotation
+    let location = error.location().unwrap();
+    assert_eq!(location.line(), 1);
+    assert_eq!(location.column(), 8);
+
+    assert_eq!(
+        format(code, LuaVersion::Luau).unwrap(),
+        "local x: number = 1\n"
+    );
+    assert_eq!(
+        format(code, LuaVersion::All).unwrap(),
+        "local x: number = 1\n"
+    );
+}
+
+#[test]
+#[cfg(feature = "luau")]
+fn test_luau_statements_rejected_as_lua51() {
+    for code in &[
+        "type Foo = number\n",
+        "export type Foo = number\n",
+        "x += 1\n",
+        "local x = y :: number\n",
+        "for i = 1, 10 do\n\tcontinue\nend\n",
+        "local function foo<T>(x) end\n",
+    ] {
+        assert!(
+            matches!(
+                format(code, LuaVersion::Lua51),
+                Err(Error::ParseError { .. })
+            ),
+            "{}",
+            code
+        );
+        assert!(format(code, LuaVersion::Luau).is_ok(), "{}", code);
+    }
+}
+
+#[test]
+#[cfg(feature = "lua52")]
+fn test_goto_rejected_as_lua51() {
+    let code = "goto continue\n::continue::\n";
+    let error = format(code, LuaVersion::Lua51).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("`goto` statements are Lua52 syntax"));
+
+    assert!(format(code, LuaVersion::Lua52).is_ok());
+    assert!(format(code, LuaVersion::All).is_ok());
+}
+
+#[test]
+#[cfg(all(feature = "luau", feature = "lua52"))]
+fn test_dialects_reject_each_other() {
+    let error = format("::label::\n", LuaVersion::Luau).unwrap_err();
+    assert!(error.to_string().contains("labels are Lua52 syntax"));
+
+    let error = format("local x: number = 1\n", LuaVersion::Lua52).unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("type annotations are Luau syntax"));
+}
+
+#[test]
+fn test_lua51_syntax_accepted() {
+    let code = "local x = 1\n";
+    assert_eq!(format(code, LuaVersion::Lua51).unwrap(), code);
+}